@@ -0,0 +1,129 @@
+//! Times random lookups with `curve::evaluate`, a `BakedCurve` and `curve::evaluate_many` at a few curve sizes.
+//! Run it with `cargo bench -p curve`, which builds it optimized.
+
+use std::time::{Duration, Instant};
+
+use curve::{vec2, AnimationKey, BakedCurve};
+
+/// curve sizes the benchmark runs at
+const KEY_COUNTS: [usize; 4] = [10, 100, 1_000, 10_000];
+/// random lookups timed at each curve size
+const LOOKUPS: usize = 20_000;
+
+/// Timings of a batch of lookups with `curve::evaluate` and with a `BakedCurve` on the same keys
+struct BenchmarkResult {
+    key_count: usize,
+    lookups: usize,
+    evaluate: Duration,
+    baked: Duration,
+    /// largest difference seen between the two
+    max_difference: f32,
+    /// `curve::evaluate_many` on the same random lookups
+    many: Duration,
+    /// `curve::evaluate_many` on the lookups sorted
+    many_sorted: Duration,
+    /// `curve::evaluate_many` results that were not bit for bit the same as `curve::evaluate`
+    many_mismatches: usize,
+}
+
+/// Times `lookups` evaluations at random x of a wiggly curve with `key_count` keys. Baking is not included in the
+/// baked timing. Batch evaluation is timed on the random lookups and again with them sorted.
+fn benchmark(key_count: usize, lookups: usize) -> BenchmarkResult {
+    let key_count = key_count.max(2);
+    let width = 1.0 / (key_count - 1) as f32;
+    let points: Vec<AnimationKey> = (0..key_count)
+        .map(|i| {
+            let x = i as f32 * width;
+            let mut key = AnimationKey::new(vec2(x, (x * 37.0).sin()), width / 3.0);
+            key.tangent_out.y = (x * 11.0).cos() * width / 3.0;
+            key.tangent_in.y = -key.tangent_out.y;
+            key
+        })
+        .collect();
+
+    // xorshift so the lookups jump around the curve like independent particles would
+    let mut state = 0x2545_f491u32;
+    let xs: Vec<f32> = (0..lookups)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state >> 8) as f32 / (1 << 24) as f32
+        })
+        .collect();
+
+    let started = Instant::now();
+    let expected: Vec<f32> = xs.iter().map(|x| curve::evaluate(&points, *x)).collect();
+    let evaluate = started.elapsed();
+
+    let baked_curve = BakedCurve::new(&points);
+    let started = Instant::now();
+    let actual: Vec<f32> = xs.iter().map(|x| baked_curve.evaluate(*x)).collect();
+    let baked = started.elapsed();
+
+    let max_difference = expected
+        .iter()
+        .zip(&actual)
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f32::max);
+
+    let mut batch = vec![0.0; xs.len()];
+    let started = Instant::now();
+    curve::evaluate_many(&points, &xs, &mut batch);
+    let many = started.elapsed();
+    let mut many_mismatches = expected
+        .iter()
+        .zip(&batch)
+        .filter(|(a, b)| a.to_bits() != b.to_bits())
+        .count();
+
+    let mut sorted = xs;
+    sorted.sort_by(f32::total_cmp);
+    let started = Instant::now();
+    curve::evaluate_many(&points, &sorted, &mut batch);
+    let many_sorted = started.elapsed();
+    many_mismatches += sorted
+        .iter()
+        .zip(&batch)
+        .filter(|(x, b)| curve::evaluate(&points, **x).to_bits() != b.to_bits())
+        .count();
+
+    BenchmarkResult {
+        key_count,
+        lookups,
+        evaluate,
+        baked,
+        max_difference,
+        many,
+        many_sorted,
+        many_mismatches,
+    }
+}
+
+fn main() {
+    let headings = [
+        "Keys",
+        "Lookups",
+        "evaluate",
+        "BakedCurve",
+        "Max Difference",
+        "evaluate_many",
+        "Sorted",
+        "Mismatches",
+    ];
+    println!("{}", headings.map(|heading| format!("{heading:>16}")).join(""));
+    for key_count in KEY_COUNTS {
+        let result = benchmark(key_count, LOOKUPS);
+        let cells = [
+            result.key_count.to_string(),
+            result.lookups.to_string(),
+            format!("{:.2?}", result.evaluate),
+            format!("{:.2?}", result.baked),
+            format!("{:e}", result.max_difference),
+            format!("{:.2?}", result.many),
+            format!("{:.2?}", result.many_sorted),
+            result.many_mismatches.to_string(),
+        ];
+        println!("{}", cells.map(|cell| format!("{cell:>16}")).join(""));
+    }
+}
@@ -0,0 +1,126 @@
+use alloc::vec::Vec;
+
+use crate::key::AnimationKey;
+use crate::real::Real;
+use crate::spline::evaluate_pair_bezier;
+use crate::vec2::Vec2;
+
+/// Distances along a polyline that approximates a curve or path, for moving along it at constant speed. The denser
+/// the points the closer `length` gets to the true length, which it always slightly underestimates.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ArcLengthTable<T = f32> {
+    points: Vec<Vec2<T>>,
+    /// distance from the first point to each point, ascending
+    distances: Vec<T>,
+}
+
+impl<T: Real> ArcLengthTable<T> {
+    pub fn new(points: Vec<Vec2<T>>) -> Self {
+        let mut distances = Vec::with_capacity(points.len());
+        let mut total = T::ZERO;
+        for (i, point) in points.iter().enumerate() {
+            if i > 0 {
+                total += (*point - points[i - 1]).length();
+            }
+            distances.push(total);
+        }
+        Self { points, distances }
+    }
+
+    /// a table over `samples_per_segment + 1` points on each segment of a curve sorted by x, at evenly spaced t
+    pub fn from_curve(keys: &[AnimationKey<T>], samples_per_segment: usize) -> Self {
+        let samples = samples_per_segment.max(1);
+        let mut points = Vec::with_capacity(keys.len().saturating_sub(1) * samples + 1);
+        points.extend(keys.first().map(|key| key.pos));
+        for pair in keys.windows(2) {
+            for i in 1..=samples {
+                points.push(evaluate_pair_bezier(
+                    &pair[0],
+                    &pair[1],
+                    T::from_f64(i as f64 / samples as f64),
+                ));
+            }
+        }
+        Self::new(points)
+    }
+
+    pub fn points(&self) -> &[Vec2<T>] {
+        &self.points
+    }
+
+    /// distance from the first point to each point
+    pub fn distances(&self) -> &[T] {
+        &self.distances
+    }
+
+    pub fn length(&self) -> T {
+        self.distances.last().copied().unwrap_or(T::ZERO)
+    }
+
+    /// the point `distance` along the polyline, clamped to its ends. A NaN distance returns the first point.
+    pub fn point_at_distance(&self, distance: T) -> Vec2<T> {
+        let (Some(first), Some(last)) = (self.points.first(), self.points.last()) else {
+            return Vec2::ZERO;
+        };
+        if distance <= T::ZERO || distance.partial_cmp(&T::ZERO).is_none() {
+            return *first;
+        }
+        if distance >= self.length() {
+            return *last;
+        }
+
+        // the first point at or past `distance`, which is never the first point
+        let index = self.distances.partition_point(|d| *d < distance);
+        let (start, end) = (self.distances[index - 1], self.distances[index]);
+        let t = if end > start {
+            (distance - start) / (end - start)
+        } else {
+            T::ZERO
+        };
+        self.points[index - 1].lerp(self.points[index], t)
+    }
+
+    /// `count` points spaced evenly along the polyline, including both ends
+    pub fn even_points(&self, count: usize) -> Vec<Vec2<T>> {
+        let length = self.length();
+        match count {
+            0 => Vec::new(),
+            1 => self.points.first().copied().into_iter().collect(),
+            _ => (0..count)
+                .map(|i| self.point_at_distance(length * T::from_f64(i as f64 / (count - 1) as f64)))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::vec2::vec2;
+
+    /// an L from (0, 0) to (3, 0) to (3, 4), 7 long
+    fn table() -> ArcLengthTable<f64> {
+        ArcLengthTable::new(vec![vec2(0.0, 0.0), vec2(3.0, 0.0), vec2(3.0, 4.0)])
+    }
+
+    #[test]
+    fn point_at_distance_walks_the_polyline() {
+        let table = table();
+        assert_eq!(table.length(), 7.0);
+        assert_eq!(table.point_at_distance(1.5), vec2(1.5, 0.0));
+        assert_eq!(table.point_at_distance(3.0), vec2(3.0, 0.0));
+        assert_eq!(table.point_at_distance(5.0), vec2(3.0, 2.0));
+    }
+
+    #[test]
+    fn point_at_distance_clamps_non_finite_distances() {
+        let table = table();
+        assert_eq!(table.point_at_distance(f64::NAN), vec2(0.0, 0.0));
+        assert_eq!(table.point_at_distance(f64::NEG_INFINITY), vec2(0.0, 0.0));
+        assert_eq!(table.point_at_distance(f64::INFINITY), vec2(3.0, 4.0));
+        assert_eq!(table.point_at_distance(-1.0), vec2(0.0, 0.0));
+        assert_eq!(table.point_at_distance(8.0), vec2(3.0, 4.0));
+    }
+}
@@ -0,0 +1,353 @@
+use alloc::vec::Vec;
+
+use crate::key::AnimationKey;
+use crate::real::Real;
+
+/// A curve flattened into one cubic polynomial per segment for fast evaluation. `evaluate` maps x to t
+/// linearly so every segment is exactly a cubic in `x - start`, and baking just folds the Bézier weights and the
+/// 1 / width scale into its coefficients. Lookups are a binary search over the segment starts. Like `evaluate`, an x
+/// where two segments meet belongs to the one ending there, so stacked keys read the lower key.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BakedCurve<T = f32> {
+    /// x where each segment starts, ascending
+    starts: Vec<T>,
+    /// power basis coefficients of each segment in `x - start`, lowest degree first
+    coefficients: Vec<[T; 4]>,
+    /// x and y of the first key, which the curve holds before
+    start: (T, T),
+    /// x and y of the last key, which the curve holds past
+    end: (T, T),
+}
+
+impl<T: Real> BakedCurve<T> {
+    /// bakes keys that are sorted by x
+    pub fn new(points: &[AnimationKey<T>]) -> Self {
+        let (Some(first), Some(last)) = (points.first(), points.last()) else {
+            return Self::default();
+        };
+
+        let mut starts = Vec::with_capacity(points.len());
+        let mut coefficients = Vec::with_capacity(points.len());
+        for pair in points.windows(2) {
+            let width = pair[1].pos.x - pair[0].pos.x;
+            // zero width segments can never be looked up, since their x belongs to the segment ending there
+            if width <= T::ZERO {
+                continue;
+            }
+
+            let p0 = pair[0].pos.y;
+            let p1 = pair[0].tangent_out_world().y;
+            let p2 = pair[1].tangent_in_world().y;
+            let p3 = pair[1].pos.y;
+            let (two, three) = (T::from(2.0), T::from(3.0));
+            let inv_width = T::ONE / width;
+            starts.push(pair[0].pos.x);
+            coefficients.push([
+                p0,
+                three * (p1 - p0) * inv_width,
+                three * (p0 - two * p1 + p2) * inv_width * inv_width,
+                (p3 - three * p2 + three * p1 - p0) * inv_width * inv_width * inv_width,
+            ]);
+        }
+
+        // a lone key or keys stacked at one x are just a constant
+        if starts.is_empty() {
+            starts.push(last.pos.x);
+            coefficients.push([last.pos.y, T::ZERO, T::ZERO, T::ZERO]);
+        }
+
+        Self {
+            starts,
+            coefficients,
+            start: (first.pos.x, first.pos.y),
+            end: (last.pos.x, last.pos.y),
+        }
+    }
+
+    /// same result as `evaluate` up to rounding, holding the first and last key values past the ends
+    pub fn evaluate(&self, x: T) -> T {
+        if self.starts.is_empty() {
+            return T::ZERO;
+        }
+        if x <= self.start.0 {
+            return self.start.1;
+        }
+        if x >= self.end.0 {
+            return self.end.1;
+        }
+        self.evaluate_segment(self.find_segment(x), x)
+    }
+
+    /// x of the first and last key
+    pub fn domain(&self) -> (T, T) {
+        (self.start.0, self.end.0)
+    }
+
+    /// y of the first and last key
+    fn end_values(&self) -> (T, T) {
+        (self.start.1, self.end.1)
+    }
+
+    /// index of the segment containing `x` by binary search, which is the one ending at `x` when it is a segment start
+    fn find_segment(&self, x: T) -> usize {
+        self.starts.partition_point(|start| *start < x).saturating_sub(1)
+    }
+
+    /// whether `x` is after the start of `segment` and no further than its end
+    fn in_segment(&self, segment: usize, x: T) -> bool {
+        self.starts[segment] < x && self.starts.get(segment + 1).map_or(true, |next| x <= *next)
+    }
+
+    /// evaluates segment `segment` at `x`, clamping x to the start of the first segment
+    fn evaluate_segment(&self, segment: usize, x: T) -> T {
+        let [c0, c1, c2, c3] = self.coefficients[segment];
+        let s = (x - self.starts[segment]).max(T::ZERO);
+        ((c3 * s + c2) * s + c1) * s + c0
+    }
+}
+
+/// How a `CurveCursor` or a cycles [`Modifier`](crate::Modifier) maps x outside the keys back onto the curve
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WrapMode {
+    /// hold the first and last key values
+    #[default]
+    Clamp,
+    /// start over from the first key
+    Repeat,
+    /// play backwards to the first key then forwards again
+    PingPong,
+    /// start over from the first key, shifted so every cycle continues where the last one ended
+    RepeatWithOffset,
+}
+
+impl WrapMode {
+    pub const ALL: [WrapMode; 4] = [
+        WrapMode::Clamp,
+        WrapMode::Repeat,
+        WrapMode::PingPong,
+        WrapMode::RepeatWithOffset,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WrapMode::Clamp => "Clamp",
+            WrapMode::Repeat => "Repeat",
+            WrapMode::PingPong => "Ping Pong",
+            WrapMode::RepeatWithOffset => "Repeat With Offset",
+        }
+    }
+
+    /// `x` mapped into keys from `start` to `end` and how much to add to the value found there, given the y of the
+    /// first and last key
+    pub fn wrap<T: Real>(self, x: T, (start, end): (T, T), (first_y, last_y): (T, T)) -> (T, T) {
+        let period = end - start;
+        if period <= T::ZERO || (start..=end).contains(&x) {
+            return (x, T::ZERO);
+        }
+
+        match self {
+            WrapMode::Clamp => (x.max(start).min(end), T::ZERO),
+            WrapMode::Repeat => (start + (x - start).rem_euclid(period), T::ZERO),
+            WrapMode::PingPong => {
+                let phase = (x - start).rem_euclid(T::from(2.0) * period);
+                (start + period - (phase - period).abs(), T::ZERO)
+            }
+            WrapMode::RepeatWithOffset => {
+                let cycles = ((x - start) / period).floor();
+                (start + (x - start).rem_euclid(period), cycles * (last_y - first_y))
+            }
+        }
+    }
+}
+
+/// Evaluates a `BakedCurve` at x values that mostly move a little at a time, like playback time, by starting from
+/// the segment of the previous lookup. Stepping to the neighbouring segment is O(1) and anything further falls back to
+/// a binary search, so backwards seeks and wrapping around are fine. A cursor does not borrow the curve, which keeps
+/// it small enough to store per particle, but it must only be used with one curve.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub struct CurveCursor {
+    pub wrap: WrapMode,
+    segment: usize,
+}
+
+impl CurveCursor {
+    pub fn new(wrap: WrapMode) -> Self {
+        Self { wrap, segment: 0 }
+    }
+
+    /// the curve value at `x` after wrapping it into the keys
+    pub fn sample<T: Real>(&mut self, curve: &BakedCurve<T>, x: T) -> T {
+        if curve.starts.is_empty() {
+            return T::ZERO;
+        }
+        let (x, offset) = self.wrap_x(curve, x);
+        let last = curve.starts.len() - 1;
+        if x <= curve.start.0 {
+            self.segment = 0;
+            return curve.start.1 + offset;
+        }
+        if x >= curve.end.0 {
+            self.segment = last;
+            return curve.end.1 + offset;
+        }
+
+        let mut segment = self.segment.min(last);
+        if !curve.in_segment(segment, x) {
+            if segment < last && curve.in_segment(segment + 1, x) {
+                segment += 1;
+            } else if segment > 0 && curve.in_segment(segment - 1, x) {
+                segment -= 1;
+            } else {
+                segment = curve.find_segment(x);
+            }
+        }
+        self.segment = segment;
+        curve.evaluate_segment(segment, x) + offset
+    }
+
+    /// `x` mapped into the keys and how much to add to the value found there
+    pub fn wrap_x<T: Real>(&self, curve: &BakedCurve<T>, x: T) -> (T, T) {
+        self.wrap.wrap(x, curve.domain(), curve.end_values())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::vec2::vec2;
+
+    /// unevenly spaced keys with broken tangents, from x = -1 to 2
+    fn curve() -> BakedCurve<f64> {
+        let mut keys = vec![
+            AnimationKey::new(vec2(-1.0, 0.5), 0.1),
+            AnimationKey::new(vec2(0.25, 2.0), 0.2),
+            AnimationKey::new(vec2(0.3, -1.0), 0.01),
+            AnimationKey::new(vec2(1.2, 0.0), 0.3),
+            AnimationKey::new(vec2(2.0, 1.0), 0.5),
+        ];
+        keys[1].tangent_out = vec2(0.02, 0.3);
+        keys[2].tangent_in = vec2(-0.01, 0.7);
+        BakedCurve::new(&keys)
+    }
+
+    /// `count` x values from `start` to `end`, including both
+    fn grid(start: f64, end: f64, count: usize) -> Vec<f64> {
+        (0..count)
+            .map(|i| start + (end - start) * i as f64 / (count - 1) as f64)
+            .collect()
+    }
+
+    /// samples `xs` in order with one cursor and checks every value against a binary search of the wrapped x
+    fn assert_cursor_matches(wrap: WrapMode, xs: &[f64]) {
+        let curve = curve();
+        let mut cursor = CurveCursor::new(wrap);
+        for &x in xs {
+            let (wrapped, offset) = wrap.wrap(x, curve.domain(), curve.end_values());
+            let expected = curve.evaluate(wrapped) + offset;
+            assert_eq!(
+                cursor.sample(&curve, x).to_bits(),
+                expected.to_bits(),
+                "{wrap:?} at x = {x}"
+            );
+        }
+    }
+
+    /// keys with two stacked at the start, three in the middle and two at the end
+    fn stacked_keys() -> Vec<AnimationKey<f64>> {
+        let mut keys = vec![
+            AnimationKey::new(vec2(0.0, -1.0), 0.1),
+            AnimationKey::new(vec2(0.0, 0.0), 0.1),
+            AnimationKey::new(vec2(0.5, 0.25), 0.1),
+            AnimationKey::new(vec2(0.5, 1.0), 0.1),
+            AnimationKey::new(vec2(0.5, 0.75), 0.1),
+            AnimationKey::new(vec2(1.0, 1.0), 0.1),
+            AnimationKey::new(vec2(1.0, 2.0), 0.1),
+        ];
+        keys[0].tangent_out = vec2(0.0, 0.0);
+        keys[2].tangent_out = vec2(0.0, 0.0);
+        keys
+    }
+
+    #[test]
+    fn stacked_keys_match_evaluate() {
+        let keys = stacked_keys();
+        let curve = BakedCurve::new(&keys);
+        let xs = grid(-0.5, 1.5, 401).into_iter().chain(keys.iter().map(|key| key.pos.x));
+        for x in xs {
+            let expected = crate::spline::evaluate(&keys, x);
+            assert!((curve.evaluate(x) - expected).abs() < 1e-12, "x = {x}");
+        }
+        // the lower key wins where keys are stacked, as in `evaluate`
+        assert_eq!(curve.evaluate(0.0), -1.0);
+        assert!((curve.evaluate(0.5) - 0.25).abs() < 1e-12);
+        assert_eq!(curve.evaluate(1.0), 2.0);
+        assert_eq!(curve.domain(), (0.0, 1.0));
+    }
+
+    #[test]
+    fn cursor_matches_evaluate_on_stacked_keys() {
+        let curve = BakedCurve::new(&stacked_keys());
+        for wrap in WrapMode::ALL {
+            let mut cursor = CurveCursor::new(wrap);
+            for x in grid(-2.0, 3.0, 1001).into_iter().chain(grid(3.0, -2.0, 1001)) {
+                let (wrapped, offset) = wrap.wrap(x, curve.domain(), curve.end_values());
+                let expected = curve.evaluate(wrapped) + offset;
+                assert_eq!(
+                    cursor.sample(&curve, x).to_bits(),
+                    expected.to_bits(),
+                    "{wrap:?} at x = {x}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_matches_evaluate_forwards() {
+        assert_cursor_matches(WrapMode::Clamp, &grid(-1.5, 2.5, 1001));
+    }
+
+    #[test]
+    fn cursor_matches_evaluate_backwards() {
+        assert_cursor_matches(WrapMode::Clamp, &grid(2.5, -1.5, 1001));
+    }
+
+    #[test]
+    fn cursor_matches_evaluate_wrapped() {
+        for wrap in [WrapMode::Repeat, WrapMode::PingPong, WrapMode::RepeatWithOffset] {
+            assert_cursor_matches(wrap, &grid(-7.0, 11.0, 4001));
+            assert_cursor_matches(wrap, &grid(11.0, -7.0, 4001));
+        }
+    }
+
+    #[test]
+    fn cursor_matches_evaluate_after_jumps() {
+        // an LCG so the lookups land in far apart segments
+        let mut state = 0x2545_f491u64;
+        let xs: Vec<f64> = (0..1000)
+            .map(|_| {
+                state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+                (state >> 11) as f64 / (1u64 << 53) as f64 * 12.0 - 5.0
+            })
+            .collect();
+        for wrap in WrapMode::ALL {
+            assert_cursor_matches(wrap, &xs);
+        }
+    }
+
+    #[test]
+    fn cursor_lands_on_segment_starts() {
+        let curve = curve();
+        let mut cursor = CurveCursor::new(WrapMode::Clamp);
+        for x in [-1.0, 0.25, 0.3, 1.2, 2.0, 1.2, 0.3, 0.25, -1.0] {
+            assert_eq!(
+                cursor.sample(&curve, x).to_bits(),
+                curve.evaluate(x).to_bits(),
+                "x = {x}"
+            );
+        }
+    }
+}
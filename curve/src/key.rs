@@ -0,0 +1,63 @@
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::real::Real;
+use crate::tcb::Tcb;
+use crate::vec2::{vec2, Vec2};
+
+static NEXT_KEY_ID: AtomicUsize = AtomicUsize::new(1);
+
+fn next_key_id() -> u64 {
+    NEXT_KEY_ID.fetch_add(1, Ordering::Relaxed) as u64
+}
+
+/// A key on a curve. The tangents are the Bézier handles relative to `pos`, which [`crate::apply_tcb`] sets for keys with
+/// TCB controls.
+#[derive(Default, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnimationKey<T = f32> {
+    /// unique per key so it can be tracked through re-sorting even when two keys are otherwise identical. Loaded keys
+    /// get fresh ids.
+    #[cfg_attr(feature = "serde", serde(skip, default = "next_key_id"))]
+    pub id: u64,
+    pub pos: Vec2<T>,
+    pub tangent_in: Vec2<T>,
+    pub tangent_out: Vec2<T>,
+    pub tangent_locked: bool,
+    /// makes this a TCB key, whose tangents are derived from its neighbours rather than edited
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub tcb: Option<Tcb<T>>,
+}
+
+impl<T: Real> AnimationKey<T> {
+    /// creates a key with flat tangents. `tangent_length` is in curve units along the x axis.
+    pub fn new(pos: Vec2<T>, tangent_length: T) -> AnimationKey<T> {
+        AnimationKey {
+            id: next_key_id(),
+            pos,
+            tangent_in: vec2(-tangent_length, T::ZERO),
+            tangent_out: vec2(tangent_length, T::ZERO),
+            tangent_locked: true,
+            tcb: None,
+        }
+    }
+
+    pub fn tangent_in_world(&self) -> Vec2<T> {
+        self.pos + self.tangent_in
+    }
+
+    pub fn tangent_out_world(&self) -> Vec2<T> {
+        self.pos + self.tangent_out
+    }
+
+    /// the same key in another precision, keeping its id
+    pub fn cast<U: Real>(&self) -> AnimationKey<U> {
+        AnimationKey {
+            id: self.id,
+            pos: self.pos.cast(),
+            tangent_in: self.tangent_in.cast(),
+            tangent_out: self.tangent_out.cast(),
+            tangent_locked: self.tangent_locked,
+            tcb: self.tcb.map(|tcb| tcb.cast()),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Curve model and evaluation for curves authored in the curve tool.
+//!
+//! Nothing here depends on egui so game runtimes can link it on their own. With default features off the crate is
+//! `no_std` and only needs `alloc`. The `serde` feature derives `Serialize` and `Deserialize` for the curve model so
+//! curves saved from the editor can be loaded back.
+//!
+//! A curve is a slice of [`AnimationKey`]s sorted by x. Each pair of keys is a cubic Bézier segment whose t is linear
+//! in x, so the x components of the tangent handles only matter to the editor. Vector valued curves are saved as one
+//! curve per channel with keys at the same x values, which [`VectorCurve`] combines to evaluate every channel at once.
+//!
+//! Keys with [`Tcb`] controls have tangents derived from their neighbours, which [`apply_tcb`] sets whenever keys move.
+//! The result is still Bézier segments so evaluation doesn't change.
+//!
+//! A curve can also carry a stack of [`Modifier`]s, like cycles or noise, that [`evaluate_modified`] layers on top of
+//! its keys.
+//!
+//! A [`Path`] is a free-form 2D spline through keys in any order, which can loop back and be closed. An
+//! [`ArcLengthTable`] over its points moves along it at constant speed.
+//!
+//! Everything is generic over [`Real`] and defaults to `f32`. Use `f64` keys when x spans long timelines.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod arc_length;
+mod baked;
+mod key;
+mod modifier;
+mod path;
+mod real;
+mod spline;
+mod tcb;
+mod vec2;
+mod vector;
+
+pub use arc_length::ArcLengthTable;
+pub use baked::{BakedCurve, CurveCursor, WrapMode};
+pub use key::AnimationKey;
+pub use modifier::{evaluate_modified, Modifier, ModifierKind};
+pub use path::Path;
+pub use real::Real;
+pub use spline::*;
+pub use tcb::{apply_tcb, Tcb};
+pub use vec2::{vec2, Vec2};
+pub use vector::{ChannelError, VectorCurve, VectorKey};
@@ -0,0 +1,172 @@
+use alloc::vec::Vec;
+
+use crate::baked::WrapMode;
+use crate::key::AnimationKey;
+use crate::real::Real;
+use crate::spline::evaluate;
+use crate::vec2::Vec2;
+
+/// What a [`Modifier`] does
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModifierKind<T = f32> {
+    /// repeats the keys before the first key and after the last
+    Cycles { before: WrapMode, after: WrapMode },
+    /// adds smooth random values between -`amplitude` and `amplitude` that change about `frequency` times per x unit.
+    /// The same `seed` always gives the same noise.
+    Noise { seed: u32, amplitude: T, frequency: T },
+    /// scales the value away from `reference` by a factor interpolated between `points` of x and factor, which are
+    /// sorted by x. Past the ends the first and last factors hold.
+    Envelope { reference: T, points: Vec<Vec2<T>> },
+    /// holds the value for `step` x units at a time, starting from `offset`
+    Stepped { step: T, offset: T },
+    /// keeps the value between the bounds that are set
+    Limits { min: Option<T>, max: Option<T> },
+}
+
+/// A change layered on a curve without editing its keys, like a Blender F-Curve modifier. A curve has a stack of them
+/// that [`evaluate_modified`] applies.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Modifier<T = f32> {
+    pub kind: ModifierKind<T>,
+    /// disabled modifiers are skipped
+    pub enabled: bool,
+}
+
+impl<T: Real> Modifier<T> {
+    pub fn new(kind: ModifierKind<T>) -> Self {
+        Self { kind, enabled: true }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.kind {
+            ModifierKind::Cycles { .. } => "Cycles",
+            ModifierKind::Noise { .. } => "Noise",
+            ModifierKind::Envelope { .. } => "Envelope",
+            ModifierKind::Stepped { .. } => "Stepped",
+            ModifierKind::Limits { .. } => "Limits",
+        }
+    }
+
+    /// the same modifier in another precision
+    pub fn cast<U: Real>(&self) -> Modifier<U> {
+        let cast = |value: T| U::from_f64(value.to_f64());
+        let kind = match &self.kind {
+            ModifierKind::Cycles { before, after } => ModifierKind::Cycles {
+                before: *before,
+                after: *after,
+            },
+            ModifierKind::Noise {
+                seed,
+                amplitude,
+                frequency,
+            } => ModifierKind::Noise {
+                seed: *seed,
+                amplitude: cast(*amplitude),
+                frequency: cast(*frequency),
+            },
+            ModifierKind::Envelope { reference, points } => ModifierKind::Envelope {
+                reference: cast(*reference),
+                points: points.iter().map(|point| point.cast()).collect(),
+            },
+            ModifierKind::Stepped { step, offset } => ModifierKind::Stepped {
+                step: cast(*step),
+                offset: cast(*offset),
+            },
+            ModifierKind::Limits { min, max } => ModifierKind::Limits {
+                min: min.map(cast),
+                max: max.map(cast),
+            },
+        };
+        Modifier {
+            kind,
+            enabled: self.enabled,
+        }
+    }
+}
+
+/// Evaluates `keys` at `x` with the enabled `modifiers` applied. As in Blender, the modifiers that change time,
+/// cycles and stepped, map x before the keys are evaluated, bottom of the stack first, and the rest change the value
+/// top first. Noise and envelopes follow the original x so they don't repeat with the cycles.
+pub fn evaluate_modified<T: Real>(keys: &[AnimationKey<T>], modifiers: &[Modifier<T>], x: T) -> T {
+    let (Some(first), Some(last)) = (keys.first(), keys.last()) else {
+        return T::ZERO;
+    };
+    let modifiers = || modifiers.iter().filter(|modifier| modifier.enabled);
+
+    let mut time = x;
+    let mut offset = T::ZERO;
+    for modifier in modifiers().rev() {
+        match &modifier.kind {
+            ModifierKind::Cycles { before, after } => {
+                let wrap = if time < first.pos.x { before } else { after };
+                let (wrapped, cycle_offset) = wrap.wrap(time, (first.pos.x, last.pos.x), (first.pos.y, last.pos.y));
+                time = wrapped;
+                offset += cycle_offset;
+            }
+            ModifierKind::Stepped { step, offset: start } if *step > T::ZERO => {
+                time = *start + ((time - *start) / *step).floor() * *step;
+            }
+            _ => {}
+        }
+    }
+
+    let mut value = evaluate(keys, time) + offset;
+    for modifier in modifiers() {
+        match &modifier.kind {
+            ModifierKind::Noise {
+                seed,
+                amplitude,
+                frequency,
+            } => value += *amplitude * T::from_f64(value_noise(*seed, (x * *frequency).to_f64())),
+            ModifierKind::Envelope { reference, points } => {
+                value = *reference + (value - *reference) * envelope_factor(points, x);
+            }
+            ModifierKind::Limits { min, max } => {
+                if let Some(min) = min {
+                    value = value.max(*min);
+                }
+                if let Some(max) = max {
+                    value = value.min(*max);
+                }
+            }
+            _ => {}
+        }
+    }
+    value
+}
+
+/// the factor of an envelope at `x`, 1 without any points
+fn envelope_factor<T: Real>(points: &[Vec2<T>], x: T) -> T {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return T::ONE;
+    };
+    if x <= first.x {
+        return first.y;
+    }
+    if x >= last.x {
+        return last.y;
+    }
+    let index = points.partition_point(|point| point.x <= x);
+    let (a, b) = (points[index - 1], points[index]);
+    a.y + (b.y - a.y) * (x - a.x) / (b.x - a.x)
+}
+
+/// smooth noise between -1 and 1 through a random value at every whole x
+fn value_noise(seed: u32, x: f64) -> f64 {
+    let cell = Real::floor(x);
+    let f = x - cell;
+    let t = f * f * (3.0 - 2.0 * f);
+    let (a, b) = (lattice(seed, cell as i64), lattice(seed, cell as i64 + 1));
+    a + (b - a) * t
+}
+
+/// a random value between -1 and 1 for a whole x, from the splitmix64 finaliser
+fn lattice(seed: u32, cell: i64) -> f64 {
+    let mut h = (cell as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ (seed as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    h = (h ^ (h >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    h ^= h >> 31;
+    (h >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+}
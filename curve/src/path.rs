@@ -0,0 +1,85 @@
+use alloc::vec::Vec;
+
+use crate::arc_length::ArcLengthTable;
+use crate::key::AnimationKey;
+use crate::real::Real;
+use crate::spline::evaluate_pair_bezier;
+use crate::vec2::Vec2;
+
+/// A free-form 2D path, like a camera rail, through keys in the order they were placed. Unlike a curve the keys are
+/// not sorted by x so the path can loop back on itself. Each pair of keys is a cubic Bézier in both x and y, and a
+/// closed path has one more segment from the last key back to the first.
+///
+/// The path parameter `u` runs from 0 at the first key to `segment_count()` at the end, one unit per segment. Speed
+/// along `u` changes with the handles, so use an [`ArcLengthTable`] to move at a constant speed.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Path<T = f32> {
+    pub keys: Vec<AnimationKey<T>>,
+    pub closed: bool,
+}
+
+impl<T: Real> Path<T> {
+    pub fn new(keys: Vec<AnimationKey<T>>, closed: bool) -> Self {
+        Self { keys, closed }
+    }
+
+    pub fn segment_count(&self) -> usize {
+        match self.keys.len() {
+            0 | 1 => 0,
+            n if self.closed => n,
+            n => n - 1,
+        }
+    }
+
+    /// the keys at the start and end of segment `index`
+    pub fn segment(&self, index: usize) -> (&AnimationKey<T>, &AnimationKey<T>) {
+        (&self.keys[index], &self.keys[(index + 1) % self.keys.len()])
+    }
+
+    /// the point at path parameter `u`, clamped to the ends of the path
+    pub fn evaluate(&self, u: T) -> Vec2<T> {
+        let count = self.segment_count();
+        if count == 0 {
+            return self.keys.first().map_or(Vec2::ZERO, |key| key.pos);
+        }
+
+        let u = u.max(T::ZERO).min(T::from_f64(count as f64));
+        let index = (u.floor().to_f64() as usize).min(count - 1);
+        let (start, end) = self.segment(index);
+        evaluate_pair_bezier(start, end, u - T::from_f64(index as f64))
+    }
+
+    /// `samples_per_segment + 1` points on each segment at evenly spaced parameter values, sharing the points where
+    /// segments meet
+    pub fn sample(&self, samples_per_segment: usize) -> Vec<Vec2<T>> {
+        let count = self.segment_count();
+        let samples = samples_per_segment.max(1);
+        if count == 0 {
+            return self.keys.first().map(|key| key.pos).into_iter().collect();
+        }
+
+        let mut points = Vec::with_capacity(count * samples + 1);
+        points.push(self.keys[0].pos);
+        for index in 0..count {
+            let (start, end) = self.segment(index);
+            for i in 1..=samples {
+                points.push(evaluate_pair_bezier(start, end, T::from_f64(i as f64 / samples as f64)));
+            }
+        }
+        points
+    }
+
+    /// an arc length table over `sample(samples_per_segment)`
+    pub fn arc_length_table(&self, samples_per_segment: usize) -> ArcLengthTable<T> {
+        ArcLengthTable::new(self.sample(samples_per_segment))
+    }
+
+    /// the same path in another precision
+    pub fn cast<U: Real>(&self) -> Path<U> {
+        Path {
+            keys: self.keys.iter().map(|key| key.cast()).collect(),
+            closed: self.closed,
+        }
+    }
+}
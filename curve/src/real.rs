@@ -0,0 +1,151 @@
+use core::fmt::{Debug, Display};
+use core::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// The float types a curve can be built on, `f32` and `f64`. Curves default to `f32`, which is what runtimes usually
+/// want. `f64` keeps long timelines, like a day in seconds, from losing precision.
+pub trait Real:
+    Copy
+    + Default
+    + PartialOrd
+    + Debug
+    + Display
+    + From<f32>
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + 'static
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const EPSILON: Self;
+    const MAX: Self;
+
+    /// rounds to the nearest value for `f32`
+    fn from_f64(value: f64) -> Self;
+    fn to_f64(self) -> f64;
+    /// rounds to the nearest value for `f64`
+    fn to_f32(self) -> f32;
+
+    fn abs(self) -> Self;
+    fn floor(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn rem_euclid(self, rhs: Self) -> Self;
+    fn min(self, other: Self) -> Self;
+    fn max(self, other: Self) -> Self;
+    fn is_finite(self) -> bool;
+}
+
+macro_rules! impl_real {
+    ($float:ident, $sign_mask:expr, $whole_above:expr, $sqrt_magic:expr) => {
+        impl Real for $float {
+            const ZERO: Self = 0.0;
+            const ONE: Self = 1.0;
+            const EPSILON: Self = $float::EPSILON;
+            const MAX: Self = $float::MAX;
+
+            fn from_f64(value: f64) -> Self {
+                value as $float
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn to_f32(self) -> f32 {
+                self as f32
+            }
+
+            #[cfg(feature = "std")]
+            fn abs(self) -> Self {
+                $float::abs(self)
+            }
+
+            #[cfg(not(feature = "std"))]
+            fn abs(self) -> Self {
+                $float::from_bits(self.to_bits() & !$sign_mask)
+            }
+
+            #[cfg(feature = "std")]
+            fn floor(self) -> Self {
+                $float::floor(self)
+            }
+
+            #[cfg(not(feature = "std"))]
+            fn floor(self) -> Self {
+                // past the mantissa every value is a whole number
+                if !self.is_finite() || Real::abs(self) >= $whole_above {
+                    return self;
+                }
+                let truncated = self as i64 as $float;
+                if truncated > self {
+                    truncated - 1.0
+                } else {
+                    truncated
+                }
+            }
+
+            #[cfg(feature = "std")]
+            fn sqrt(self) -> Self {
+                $float::sqrt(self)
+            }
+
+            #[cfg(not(feature = "std"))]
+            fn sqrt(self) -> Self {
+                if self <= 0.0 || !self.is_finite() {
+                    return if self == 0.0 || self == $float::INFINITY {
+                        self
+                    } else {
+                        $float::NAN
+                    };
+                }
+                // halve the exponent for a first guess then polish it with newton steps
+                let mut y = $float::from_bits((self.to_bits() >> 1) + $sqrt_magic);
+                for _ in 0..4 {
+                    y = 0.5 * (y + self / y);
+                }
+                y
+            }
+
+            #[cfg(feature = "std")]
+            fn rem_euclid(self, rhs: Self) -> Self {
+                $float::rem_euclid(self, rhs)
+            }
+
+            #[cfg(not(feature = "std"))]
+            fn rem_euclid(self, rhs: Self) -> Self {
+                let r = self - rhs * Real::floor(self / rhs);
+                // rounding can land exactly on `rhs`
+                if r >= Real::abs(rhs) {
+                    0.0
+                } else {
+                    r
+                }
+            }
+
+            fn min(self, other: Self) -> Self {
+                $float::min(self, other)
+            }
+
+            fn max(self, other: Self) -> Self {
+                $float::max(self, other)
+            }
+
+            fn is_finite(self) -> bool {
+                $float::is_finite(self)
+            }
+        }
+    };
+}
+
+impl_real!(f32, 0x8000_0000u32, 8_388_608.0, 0x1fbd_1df5u32);
+impl_real!(
+    f64,
+    0x8000_0000_0000_0000u64,
+    4_503_599_627_370_496.0,
+    0x1ff7_a3be_a91d_9b1bu64
+);
@@ -0,0 +1,595 @@
+use alloc::vec::Vec;
+
+use crate::key::AnimationKey;
+use crate::real::Real;
+use crate::vec2::Vec2;
+
+/// number of halvings `evaluate_inverse` does, enough to exhaust f64 precision
+const BISECTION_STEPS: usize = 64;
+
+/// The value of the curve at x = `time`, holding the first and last values past the keys. A single key is a constant
+/// curve.
+///
+/// # Panics
+///
+/// If `pts` is empty.
+pub fn evaluate<T: Real>(pts: &[AnimationKey<T>], time: T) -> T {
+    if time <= pts[0].pos.x || pts.len() == 1 {
+        return pts[0].pos.y;
+    }
+    if time >= pts[pts.len() - 1].pos.x {
+        return pts[pts.len() - 1].pos.y;
+    }
+
+    let (index_1, t) = segment_at(pts, time);
+    evaluate_pair_bezier(&pts[index_1], &pts[index_1 + 1], t).y
+}
+
+/// Evaluates the curve at every x in `times`, writing the values to `out`, which must be the same length. Results are
+/// bit for bit what `evaluate` returns for each x: the same segment is picked and the same float operations run in the
+/// same order. Consecutive inputs that land in the same segment are evaluated together in a loop without branches
+/// that the compiler can vectorise, so sorted inputs are fastest. Unsorted inputs still only cost a binary search each.
+/// Keys that are not sorted by x, and NaN inputs, fall back to calling `evaluate`.
+///
+/// # Panics
+///
+/// If `times` and `out` have different lengths, or if `pts` is empty while `times` isn't.
+pub fn evaluate_many<T: Real>(pts: &[AnimationKey<T>], times: &[T], out: &mut [T]) {
+    assert_eq!(times.len(), out.len(), "evaluate_many needs an output for every input");
+    if times.is_empty() {
+        return;
+    }
+    if !pts.windows(2).all(|pair| pair[0].pos.x <= pair[1].pos.x) {
+        for (time, value) in times.iter().zip(out.iter_mut()) {
+            *value = evaluate(pts, *time);
+        }
+        return;
+    }
+
+    let first = &pts[0];
+    let last = &pts[pts.len() - 1];
+    let mut i = 0;
+    while i < times.len() {
+        let time = times[i];
+        // NaN doesn't compare so it lands in no segment
+        if time.partial_cmp(&first.pos.x).is_none() {
+            out[i] = evaluate(pts, time);
+            i += 1;
+            continue;
+        }
+        if time <= first.pos.x {
+            out[i] = first.pos.y;
+            i += 1;
+            continue;
+        }
+        if time >= last.pos.x {
+            out[i] = last.pos.y;
+            i += 1;
+            continue;
+        }
+
+        // the first segment ending at or after `time`, which is the one the scan in `segment_at` stops at. `time` is
+        // inside it, so the run below always takes at least this input.
+        let index_1 = pts[1..].partition_point(|pt| pt.pos.x < time);
+        let (pt1, pt2) = (&pts[index_1], &pts[index_1 + 1]);
+        let (start, end) = (pt1.pos.x, pt2.pos.x);
+        let run = times[i..]
+            .iter()
+            .position(|t| !(*t > start && *t <= end && *t < last.pos.x))
+            .unwrap_or(times.len() - i);
+
+        let delta = end - start;
+        let three = T::from(3.0);
+        let p0 = pt1.pos.y;
+        let p1 = pt1.tangent_out_world().y;
+        let p2 = pt2.tangent_in_world().y;
+        let p3 = pt2.pos.y;
+        for (time, value) in times[i..i + run].iter().zip(&mut out[i..i + run]) {
+            // matches `evaluate_pair_bezier` operation for operation
+            let t = (*time - start) / delta;
+            let h = T::ONE - t;
+            let a = t * t * t;
+            let b = three * t * t * h;
+            let c = three * t * h * h;
+            let d = h * h * h;
+            *value = p3 * a + p2 * b + p1 * c + p0 * d;
+        }
+        i += run;
+    }
+}
+
+/// dy/dx of the curve `evaluate` describes. Flat outside the keys.
+pub fn evaluate_derivative<T: Real>(pts: &[AnimationKey<T>], time: T) -> T {
+    if pts.len() < 2 || time < pts[0].pos.x || time > pts[pts.len() - 1].pos.x {
+        return T::ZERO;
+    }
+
+    let (index_1, t) = segment_at(pts, time);
+    let (a, b, c) = derivative_coefficients(&pts[index_1], &pts[index_1 + 1]);
+    let delta = pts[index_1 + 1].pos.x - pts[index_1].pos.x;
+    T::from(3.0) * ((a * t + b) * t + c) / delta
+}
+
+/// index of the first key of the segment containing `time` and `time` translated to a 0-1 t value in that segment.
+/// `time` must be within the keys.
+fn segment_at<T: Real>(pts: &[AnimationKey<T>], time: T) -> (usize, T) {
+    // find the two pts we are looking at
+    let mut index_1 = 0;
+    for chunk in pts.windows(2) {
+        if chunk[0].pos.x <= time && chunk[1].pos.x >= time {
+            break;
+        }
+        index_1 += 1;
+    }
+    index_1 = index_1.min(pts.len() - 2);
+
+    // translate time to a new t value in a 0-1 range between these 2 points
+    let delta = pts[index_1 + 1].pos.x - pts[index_1].pos.x;
+    let t = if delta > T::ZERO {
+        (time - pts[index_1].pos.x) / delta
+    } else {
+        T::ZERO
+    };
+    (index_1, t)
+}
+
+/// Why a curve could not be inverted
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum InverseError {
+    /// the curve changes direction so a value can map back to more than one time
+    NotMonotonic,
+    /// the curve has the same value everywhere so there is no time to map back to
+    Constant,
+    /// the curve has no keys
+    Empty,
+}
+
+impl core::fmt::Display for InverseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InverseError::NotMonotonic => write!(f, "curve is not monotonic"),
+            InverseError::Constant => write!(f, "curve is constant"),
+            InverseError::Empty => write!(f, "curve has no keys"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InverseError {}
+
+/// Finds the time at which `evaluate` returns `value` by bisecting the segment containing it. Values past either end
+/// of the curve return the time of the first or last key and flat stretches return a time somewhere along them. A NaN
+/// `value` returns NaN.
+pub fn evaluate_inverse<T: Real>(pts: &[AnimationKey<T>], value: T) -> Result<T, InverseError> {
+    let (Some(first), Some(last)) = (pts.first(), pts.last()) else {
+        return Err(InverseError::Empty);
+    };
+    let rising = monotonic_direction(pts).ok_or(InverseError::NotMonotonic)?;
+    if first.pos.y == last.pos.y {
+        return Err(InverseError::Constant);
+    }
+
+    // flip falling curves so we only deal with rising values
+    let sign = if rising { T::ONE } else { -T::ONE };
+    let target = value * sign;
+    if target <= first.pos.y * sign {
+        return Ok(first.pos.x);
+    }
+    if target >= last.pos.y * sign {
+        return Ok(last.pos.x);
+    }
+
+    // only NaN, which compares false with every key, gets this far without a segment
+    let Some(index) = pts.windows(2).position(|pair| pair[1].pos.y * sign >= target) else {
+        return Ok(value);
+    };
+    let (pt1, pt2) = (&pts[index], &pts[index + 1]);
+
+    let half = T::from(0.5);
+    let (mut low, mut high) = (T::ZERO, T::ONE);
+    for _ in 0..BISECTION_STEPS {
+        let mid = half * (low + high);
+        if evaluate_pair_bezier(pt1, pt2, mid).y * sign < target {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    Ok(pt1.pos.x + (pt2.pos.x - pt1.pos.x) * half * (low + high))
+}
+
+/// true if the curve `evaluate` describes never changes direction
+pub fn curve_is_monotonic<T: Real>(pts: &[AnimationKey<T>]) -> bool {
+    monotonic_direction(pts).is_some()
+}
+
+/// `Some(true)` if the curve never falls, `Some(false)` if it never rises and `None` if it does both
+fn monotonic_direction<T: Real>(pts: &[AnimationKey<T>]) -> Option<bool> {
+    let mut rising = true;
+    let mut falling = true;
+    for pair in pts.windows(2) {
+        let (min, max) = segment_slope_extents(&pair[0], &pair[1]);
+        // slopes are in y units so scale the allowed noise with the size of the segment
+        let epsilon = T::from(1e-5) * (pair[1].pos.y - pair[0].pos.y).abs().max(T::EPSILON);
+        rising &= min >= -epsilon;
+        falling &= max <= epsilon;
+    }
+
+    match (rising, falling) {
+        (true, _) => Some(true),
+        (false, true) => Some(false),
+        (false, false) => None,
+    }
+}
+
+/// Calculate the point (x,y) at t based on the cubic Bézier curve equation.
+/// t is in [0.0,1.0]
+pub fn evaluate_pair_bezier<T: Real>(pt1: &AnimationKey<T>, pt2: &AnimationKey<T>, time: T) -> Vec2<T> {
+    let three = T::from(3.0);
+    let h = T::ONE - time;
+    let a = time * time * time;
+    let b = three * time * time * h;
+    let c = three * time * h * h;
+    let d = h * h * h;
+
+    pt2.pos * a + pt2.tangent_in_world() * b + pt1.tangent_out_world() * c + pt1.pos * d
+}
+
+/// the min and max y values the cubic Bézier segment between two keys reaches
+pub fn segment_y_extents<T: Real>(pt1: &AnimationKey<T>, pt2: &AnimationKey<T>) -> (T, T) {
+    let p0 = pt1.pos.y;
+    let p3 = pt2.pos.y;
+
+    let mut min = p0.min(p3);
+    let mut max = p0.max(p3);
+    let mut include = |t: T| {
+        if t > T::ZERO && t < T::ONE {
+            let y = evaluate_pair_bezier(pt1, pt2, t).y;
+            min = min.min(y);
+            max = max.max(y);
+        }
+    };
+
+    let (a, b, c) = derivative_coefficients(pt1, pt2);
+
+    let two = T::from(2.0);
+    if a.abs() < T::EPSILON {
+        if b.abs() > T::EPSILON {
+            include(-c / b);
+        }
+    } else {
+        let discriminant = b * b - T::from(4.0) * a * c;
+        if discriminant >= T::ZERO {
+            let root = discriminant.sqrt();
+            include((-b + root) / (two * a));
+            include((-b - root) / (two * a));
+        }
+    }
+
+    (min, max)
+}
+
+/// the min and max of dy/dt (divided by 3) over the cubic Bézier segment between two keys
+fn segment_slope_extents<T: Real>(pt1: &AnimationKey<T>, pt2: &AnimationKey<T>) -> (T, T) {
+    let (a, b, c) = derivative_coefficients(pt1, pt2);
+    let mut min = c.min(a + b + c);
+    let mut max = c.max(a + b + c);
+
+    if a != T::ZERO {
+        let t = -b / (T::from(2.0) * a);
+        if t > T::ZERO && t < T::ONE {
+            let slope = (a * t + b) * t + c;
+            min = min.min(slope);
+            max = max.max(slope);
+        }
+    }
+
+    (min, max)
+}
+
+/// the y derivative (divided by 3) of the cubic Bézier segment between two keys as the quadratic a*t^2 + b*t + c
+fn derivative_coefficients<T: Real>(pt1: &AnimationKey<T>, pt2: &AnimationKey<T>) -> (T, T, T) {
+    let p0 = pt1.pos.y;
+    let p1 = pt1.tangent_out_world().y;
+    let p2 = pt2.tangent_in_world().y;
+    let p3 = pt2.pos.y;
+
+    let (d0, d1, d2) = (p1 - p0, p2 - p1, p3 - p2);
+    let two = T::from(2.0);
+    (d0 - two * d1 + d2, two * (d1 - d0), d0)
+}
+
+/// true if the key values never change direction, in which case `make_monotonic` can produce a monotonic curve
+pub fn keys_are_monotonic<T: Real>(points: &[AnimationKey<T>]) -> bool {
+    let rising = points.windows(2).all(|pair| pair[1].pos.y >= pair[0].pos.y);
+    let falling = points.windows(2).all(|pair| pair[1].pos.y <= pair[0].pos.y);
+    rising || falling
+}
+
+/// Adjusts the tangents so every segment is monotonic using the Fritsch–Carlson conditions. The existing slopes are
+/// kept where possible: slopes against the direction of a segment are flattened and pairs outside the α² + β² ≤ 9
+/// circle are scaled down. Handles are set to a third of the segment width so each segment evaluates exactly like
+/// the cubic hermite it describes. Locked tangents keep a shared slope.
+///
+/// The curve as a whole is only monotonic if `keys_are_monotonic` holds.
+pub fn make_monotonic<T: Real>(points: &mut [AnimationKey<T>]) {
+    let three = T::from(3.0);
+    let mut out_slopes: Vec<T> = points.iter().map(|pt| tangent_slope(pt.tangent_out)).collect();
+    let mut in_slopes: Vec<T> = points.iter().map(|pt| tangent_slope(pt.tangent_in)).collect();
+
+    for i in 0..points.len().saturating_sub(1) {
+        let width = points[i + 1].pos.x - points[i].pos.x;
+        if width <= T::ZERO {
+            continue;
+        }
+
+        // slopes only ever shrink towards 0 so a shared slope stays valid for the segment before it
+        let shared = i > 0 && points[i].tangent_locked;
+        if shared {
+            out_slopes[i] = in_slopes[i];
+        }
+
+        let secant = (points[i + 1].pos.y - points[i].pos.y) / width;
+        let (start, end) = if secant == T::ZERO {
+            (T::ZERO, T::ZERO)
+        } else {
+            let alpha = (out_slopes[i] / secant).max(T::ZERO);
+            let beta = (in_slopes[i + 1] / secant).max(T::ZERO);
+            let radius_sq = alpha * alpha + beta * beta;
+            let tau = if radius_sq > T::from(9.0) {
+                three / radius_sq.sqrt()
+            } else {
+                T::ONE
+            };
+            (tau * alpha * secant, tau * beta * secant)
+        };
+
+        out_slopes[i] = start;
+        in_slopes[i + 1] = end;
+        if shared {
+            in_slopes[i] = start;
+        }
+    }
+
+    for i in 0..points.len().saturating_sub(1) {
+        let handle = (points[i + 1].pos.x - points[i].pos.x) / three;
+        if handle <= T::ZERO {
+            continue;
+        }
+        points[i].tangent_out = Vec2::new(handle, out_slopes[i] * handle);
+        points[i + 1].tangent_in = Vec2::new(-handle, -in_slopes[i + 1] * handle);
+    }
+}
+
+/// dy/dx of a tangent handle. Vertical handles are treated as flat.
+fn tangent_slope<T: Real>(tangent: Vec2<T>) -> T {
+    if tangent.x.abs() < T::EPSILON {
+        T::ZERO
+    } else {
+        tangent.y / tangent.x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::vec2::vec2;
+
+    /// unevenly spaced keys with broken and locked tangents
+    fn keys() -> Vec<AnimationKey<f64>> {
+        let mut keys = vec![
+            AnimationKey::new(vec2(-1.0, 0.5), 0.1),
+            AnimationKey::new(vec2(0.25, 2.0), 0.2),
+            AnimationKey::new(vec2(0.3, -1.0), 0.01),
+            AnimationKey::new(vec2(2.0, 1.0), 0.5),
+        ];
+        keys[1].tangent_out = vec2(0.02, 0.3);
+        keys[2].tangent_in = vec2(-0.01, 0.7);
+        keys
+    }
+
+    fn assert_matches_evaluate<T: Real>(pts: &[AnimationKey<T>], times: &[T]) {
+        let mut out = vec![T::ZERO; times.len()];
+        evaluate_many(pts, times, &mut out);
+        for (time, value) in times.iter().zip(&out) {
+            let expected = evaluate(pts, *time);
+            assert_eq!(value.to_f64().to_bits(), expected.to_f64().to_bits(), "x = {time}");
+        }
+    }
+
+    /// `count` x values from `start` to `end`, including both
+    fn grid(start: f64, end: f64, count: usize) -> Vec<f64> {
+        (0..count)
+            .map(|i| start + (end - start) * i as f64 / (count - 1) as f64)
+            .collect()
+    }
+
+    #[test]
+    fn evaluate_many_matches_sorted_inputs() {
+        let keys = keys();
+        assert_matches_evaluate(&keys, &grid(-1.0, 2.0, 1001));
+        let times: Vec<f64> = keys.iter().map(|key| key.pos.x).collect();
+        assert_matches_evaluate(&keys, &times);
+
+        let keys: Vec<AnimationKey<f32>> = keys.iter().map(|key| key.cast()).collect();
+        let times: Vec<f32> = grid(-1.0, 2.0, 1001).into_iter().map(|x| x as f32).collect();
+        assert_matches_evaluate(&keys, &times);
+    }
+
+    #[test]
+    fn evaluate_many_matches_unsorted_inputs() {
+        let mut seed = 1u64;
+        let times: Vec<f64> = (0..1000)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                (seed >> 11) as f64 / (1u64 << 53) as f64 * 3.0 - 1.0
+            })
+            .collect();
+        assert_matches_evaluate(&keys(), &times);
+
+        let mut reversed = grid(-1.0, 2.0, 101);
+        reversed.reverse();
+        assert_matches_evaluate(&keys(), &reversed);
+    }
+
+    #[test]
+    fn evaluate_many_matches_out_of_range_inputs() {
+        let times = [
+            -100.0,
+            -1.0,
+            -1.0 - 1e-12,
+            2.0,
+            2.0 + 1e-12,
+            100.0,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ];
+        assert_matches_evaluate(&keys(), &times);
+    }
+
+    #[test]
+    fn evaluate_many_matches_duplicate_x_keys() {
+        let keys = vec![
+            AnimationKey::new(vec2(0.0, 0.0), 0.1),
+            AnimationKey::new(vec2(0.5, 0.0), 0.1),
+            AnimationKey::new(vec2(0.5, 1.0), 0.1),
+            AnimationKey::new(vec2(0.5, 2.0), 0.1),
+            AnimationKey::new(vec2(1.0, 1.0), 0.1),
+        ];
+        assert_matches_evaluate(&keys, &grid(-0.5, 1.5, 401));
+        assert_matches_evaluate(&keys, &[0.5, 0.5 - 1e-15, 0.5 + 1e-15, 0.5]);
+    }
+
+    #[test]
+    fn evaluate_many_matches_a_single_key() {
+        let keys = vec![AnimationKey::new(vec2(0.5, 3.0), 0.1)];
+        assert_matches_evaluate(&keys, &[-1.0, 0.5, 2.0, f64::NAN]);
+    }
+
+    #[test]
+    fn evaluate_many_returns_nan_for_nan_inputs() {
+        let keys = keys();
+        let times = [0.5, f64::NAN, 0.2, f64::NAN, f64::NAN, 1.0];
+        assert_matches_evaluate(&keys, &times);
+
+        let mut out = [0.0; 6];
+        evaluate_many(&keys, &times, &mut out);
+        assert!(out[1].is_nan() && !out[2].is_nan());
+    }
+
+    #[test]
+    fn evaluate_many_accepts_no_inputs() {
+        evaluate_many::<f64>(&[], &[], &mut []);
+        evaluate_many(&keys(), &[], &mut []);
+    }
+
+    /// keys at `points` with flat tangents, which keep every segment between its key values
+    fn flat_keys(points: &[(f64, f64)]) -> Vec<AnimationKey<f64>> {
+        points
+            .iter()
+            .map(|&(x, y)| AnimationKey::new(vec2(x, y), 0.1))
+            .collect()
+    }
+
+    fn assert_inverse_round_trips(keys: &[AnimationKey<f64>]) {
+        let (first, last) = (keys[0].pos.y, keys[keys.len() - 1].pos.y);
+        for value in grid(first, last, 201) {
+            let x = evaluate_inverse(keys, value).unwrap();
+            assert!(
+                (evaluate(keys, x) - value).abs() < 1e-9,
+                "value {value} maps back to {x}"
+            );
+        }
+    }
+
+    #[test]
+    fn evaluate_inverse_of_rising_curve() {
+        let keys = flat_keys(&[(0.0, 0.0), (0.4, 0.3), (1.0, 1.0)]);
+        assert_inverse_round_trips(&keys);
+        assert_eq!(evaluate_inverse(&keys, -1.0), Ok(0.0));
+        assert_eq!(evaluate_inverse(&keys, 2.0), Ok(1.0));
+    }
+
+    #[test]
+    fn evaluate_inverse_of_falling_curve() {
+        let keys = flat_keys(&[(0.0, 1.0), (0.5, 0.6), (2.0, -1.0)]);
+        assert_inverse_round_trips(&keys);
+        assert_eq!(evaluate_inverse(&keys, 2.0), Ok(0.0));
+        assert_eq!(evaluate_inverse(&keys, -2.0), Ok(2.0));
+    }
+
+    #[test]
+    fn evaluate_inverse_rejects_curves_without_one_time_per_value() {
+        let hill = flat_keys(&[(0.0, 0.0), (0.5, 1.0), (1.0, 0.0)]);
+        assert_eq!(evaluate_inverse(&hill, 0.5), Err(InverseError::NotMonotonic));
+        let flat = flat_keys(&[(0.0, 0.5), (1.0, 0.5)]);
+        assert_eq!(evaluate_inverse(&flat, 0.5), Err(InverseError::Constant));
+        assert_eq!(evaluate_inverse::<f64>(&[], 0.5), Err(InverseError::Empty));
+    }
+
+    #[test]
+    fn evaluate_inverse_returns_nan_for_nan() {
+        let keys = flat_keys(&[(0.0, 0.0), (0.4, 0.3), (1.0, 1.0)]);
+        assert!(evaluate_inverse(&keys, f64::NAN).unwrap().is_nan());
+        let falling = flat_keys(&[(0.0, 1.0), (1.0, 0.0)]);
+        assert!(evaluate_inverse(&falling, f64::NAN).unwrap().is_nan());
+    }
+
+    /// keys whose tangents overshoot both ways, with a flat segment and a mix of locked and broken tangents
+    fn overshooting_keys(values: &[f64]) -> Vec<AnimationKey<f64>> {
+        let mut keys: Vec<AnimationKey<f64>> = values
+            .iter()
+            .enumerate()
+            .map(|(i, y)| AnimationKey::new(vec2(i as f64 * 0.5 + (i % 2) as f64 * 0.2, *y), 0.15))
+            .collect();
+        for (i, key) in keys.iter_mut().enumerate() {
+            let steep = if i % 2 == 0 { 3.0 } else { -2.5 };
+            key.tangent_in = vec2(-0.15, -0.15 * steep);
+            key.tangent_out = vec2(0.12, 0.12 * steep * 1.5);
+            key.tangent_locked = i % 3 == 0;
+        }
+        keys
+    }
+
+    fn assert_segments_within_keys(keys: &[AnimationKey<f64>]) {
+        for pair in keys.windows(2) {
+            let (min, max) = segment_y_extents(&pair[0], &pair[1]);
+            let (low, high) = (pair[0].pos.y.min(pair[1].pos.y), pair[0].pos.y.max(pair[1].pos.y));
+            assert!(
+                min >= low - 1e-12 && max <= high + 1e-12,
+                "{min}..{max} overshoots {low}..{high}"
+            );
+        }
+    }
+
+    #[test]
+    fn make_monotonic_keeps_segments_within_keys() {
+        let mut rising = overshooting_keys(&[0.0, 0.1, 1.0, 1.0, 1.05, 3.0]);
+        assert!(rising
+            .windows(2)
+            .any(|pair| segment_y_extents(&pair[0], &pair[1]) != (pair[0].pos.y, pair[1].pos.y)));
+        assert!(!curve_is_monotonic(&rising));
+        make_monotonic(&mut rising);
+        assert_segments_within_keys(&rising);
+        assert!(curve_is_monotonic(&rising));
+        let samples: Vec<f64> = grid(0.0, 2.5, 1001).iter().map(|x| evaluate(&rising, *x)).collect();
+        assert!(samples.windows(2).all(|pair| pair[1] >= pair[0] - 1e-12));
+
+        let mut falling = overshooting_keys(&[2.0, 1.9, 0.0, -0.5, -0.5, -4.0]);
+        make_monotonic(&mut falling);
+        assert_segments_within_keys(&falling);
+        assert!(curve_is_monotonic(&falling));
+
+        // the whole curve can't be monotonic but each segment still stays between its keys
+        let mut mixed = overshooting_keys(&[0.0, 1.0, -1.0, 2.0, 2.0, 0.5]);
+        assert!(!keys_are_monotonic(&mixed));
+        make_monotonic(&mut mixed);
+        assert_segments_within_keys(&mixed);
+    }
+}
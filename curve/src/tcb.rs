@@ -0,0 +1,132 @@
+use crate::key::AnimationKey;
+use crate::real::Real;
+use crate::vec2::vec2;
+
+/// Kochanek–Bartels controls of a key whose tangents [`apply_tcb`] derives from its neighbours, like 3ds Max's TCB
+/// controller. All zero is a Catmull-Rom spline and each is usually kept between -1 and 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tcb<T = f32> {
+    /// how tightly the curve turns at the key: 1 flattens the tangents and -1 doubles them
+    pub tension: T,
+    /// how smoothly the slope changes through the key: -1 makes a corner and 1 a kink that bulges outwards
+    pub continuity: T,
+    /// which neighbour the tangents follow: 1 carries the motion from the previous key past this one and -1 heads
+    /// for the next key early
+    pub bias: T,
+}
+
+impl<T: Real> Tcb<T> {
+    /// the same controls in another precision
+    pub fn cast<U: Real>(&self) -> Tcb<U> {
+        let cast = |value: T| U::from_f64(value.to_f64());
+        Tcb {
+            tension: cast(self.tension),
+            continuity: cast(self.continuity),
+            bias: cast(self.bias),
+        }
+    }
+}
+
+/// Sets the tangents of every key with [`Tcb`] controls from its neighbours. Handles are a third of the way to each
+/// neighbour along x, so every segment is the cubic hermite with the Kochanek–Bartels slopes, scaled for uneven key
+/// spacing as in the original paper. The first and last keys mirror their one neighbour. Other keys are left as they
+/// are and `keys` must be sorted by x.
+pub fn apply_tcb<T: Real>(keys: &mut [AnimationKey<T>]) {
+    if keys.len() < 2 {
+        return;
+    }
+    let half = T::from_f64(0.5);
+    let third = T::from_f64(1.0 / 3.0);
+    for i in 0..keys.len() {
+        let Some(tcb) = keys[i].tcb else {
+            continue;
+        };
+        let pos = keys[i].pos;
+        let (before, after) = match (i.checked_sub(1), keys.get(i + 1)) {
+            (Some(prev), Some(next)) => (pos - keys[prev].pos, next.pos - pos),
+            (Some(prev), None) => (pos - keys[prev].pos, pos - keys[prev].pos),
+            (None, Some(next)) => (next.pos - pos, next.pos - pos),
+            (None, None) => continue,
+        };
+        let span = before.x + after.x;
+        if span <= T::ZERO {
+            continue;
+        }
+
+        let one = T::ONE;
+        let (t, c, b) = (tcb.tension, tcb.continuity, tcb.bias);
+        let outgoing = ((one + b) * (one + c) * before.y + (one - b) * (one - c) * after.y) * (one - t) * half;
+        let incoming = ((one + b) * (one - c) * before.y + (one - b) * (one + c) * after.y) * (one - t) * half;
+        // the tangents are per segment, so each is scaled by the width of its segment against the average
+        let outgoing = outgoing * (after.x + after.x) / span;
+        let incoming = incoming * (before.x + before.x) / span;
+
+        let key = &mut keys[i];
+        key.tangent_out = vec2(after.x, outgoing) * third;
+        key.tangent_in = vec2(-before.x, -incoming) * third;
+        key.tangent_locked = c == T::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::spline::evaluate;
+
+    /// keys at `points` with all zero TCB controls and flat tangents for `apply_tcb` to replace
+    fn tcb_keys(points: &[(f64, f64)]) -> Vec<AnimationKey<f64>> {
+        points
+            .iter()
+            .map(|&(x, y)| {
+                let mut key = AnimationKey::new(vec2(x, y), 0.1);
+                key.tcb = Some(Tcb::default());
+                key.tangent_locked = false;
+                key
+            })
+            .collect()
+    }
+
+    #[test]
+    fn zero_controls_match_catmull_rom_tangents() {
+        let points = [(-1.0, 0.5), (0.25, 2.0), (0.5, -1.0), (2.0, 1.0), (2.5, 1.5)];
+        let mut keys = tcb_keys(&points);
+        apply_tcb(&mut keys);
+
+        for i in 1..points.len() - 1 {
+            let (prev, next) = (points[i - 1], points[i + 1]);
+            let slope = (next.1 - prev.1) / (next.0 - prev.0);
+            let key = &keys[i];
+            let (before, after) = (points[i].0 - prev.0, next.0 - points[i].0);
+            assert!((key.tangent_in.x + before / 3.0).abs() < 1e-12, "key {i}");
+            assert!((key.tangent_out.x - after / 3.0).abs() < 1e-12, "key {i}");
+            assert!((key.tangent_in.y / key.tangent_in.x - slope).abs() < 1e-12, "key {i}");
+            assert!((key.tangent_out.y / key.tangent_out.x - slope).abs() < 1e-12, "key {i}");
+            assert!(key.tangent_locked);
+        }
+    }
+
+    #[test]
+    fn zero_controls_match_uniform_catmull_rom() {
+        let ys = [0.0, 1.0, -0.5, 2.0, 0.25];
+        let points: Vec<(f64, f64)> = ys.iter().enumerate().map(|(i, &y)| (i as f64, y)).collect();
+        let mut keys = tcb_keys(&points);
+        apply_tcb(&mut keys);
+
+        for i in 1..ys.len() - 2 {
+            let (p0, p1, p2, p3) = (ys[i - 1], ys[i], ys[i + 1], ys[i + 2]);
+            for step in 0..=16 {
+                let t = step as f64 / 16.0;
+                let expected = 0.5
+                    * (2.0 * p1
+                        + (p2 - p0) * t
+                        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+                        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t * t * t);
+                let x = i as f64 + t;
+                assert!((evaluate(&keys, x) - expected).abs() < 1e-9, "x = {x}");
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use core::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+
+use crate::real::Real;
+
+/// A 2D vector in curve space, x being time and y the value
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vec2<T = f32> {
+    pub x: T,
+    pub y: T,
+}
+
+pub const fn vec2<T>(x: T, y: T) -> Vec2<T> {
+    Vec2 { x, y }
+}
+
+impl<T: Real> Vec2<T> {
+    pub const ZERO: Vec2<T> = vec2(T::ZERO, T::ZERO);
+
+    pub const fn new(x: T, y: T) -> Vec2<T> {
+        vec2(x, y)
+    }
+
+    /// converts to another precision
+    pub fn cast<U: Real>(self) -> Vec2<U> {
+        vec2(U::from_f64(self.x.to_f64()), U::from_f64(self.y.to_f64()))
+    }
+
+    pub fn length(self) -> T {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    /// the point `t` of the way from `self` to `other`
+    pub fn lerp(self, other: Vec2<T>, t: T) -> Vec2<T> {
+        self + (other - self) * t
+    }
+}
+
+impl<T> From<[T; 2]> for Vec2<T> {
+    fn from([x, y]: [T; 2]) -> Self {
+        vec2(x, y)
+    }
+}
+
+impl<T> From<Vec2<T>> for [T; 2] {
+    fn from(v: Vec2<T>) -> Self {
+        [v.x, v.y]
+    }
+}
+
+impl<T: Real> Add for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn add(self, rhs: Vec2<T>) -> Vec2<T> {
+        vec2(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl<T: Real> AddAssign for Vec2<T> {
+    fn add_assign(&mut self, rhs: Vec2<T>) {
+        *self = *self + rhs;
+    }
+}
+
+impl<T: Real> Sub for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn sub(self, rhs: Vec2<T>) -> Vec2<T> {
+        vec2(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl<T: Real> SubAssign for Vec2<T> {
+    fn sub_assign(&mut self, rhs: Vec2<T>) {
+        *self = *self - rhs;
+    }
+}
+
+impl<T: Real> Neg for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn neg(self) -> Vec2<T> {
+        vec2(-self.x, -self.y)
+    }
+}
+
+impl<T: Real> Mul<T> for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn mul(self, rhs: T) -> Vec2<T> {
+        vec2(self.x * rhs, self.y * rhs)
+    }
+}
+
+/// component wise
+impl<T: Real> Mul for Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn mul(self, rhs: Vec2<T>) -> Vec2<T> {
+        vec2(self.x * rhs.x, self.y * rhs.y)
+    }
+}
+
+/// 0 is x and 1 is y
+impl<T> Index<usize> for Vec2<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            _ => panic!("Vec2 index out of bounds: {index}"),
+        }
+    }
+}
+
+impl<T> IndexMut<usize> for Vec2<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            _ => panic!("Vec2 index out of bounds: {index}"),
+        }
+    }
+}
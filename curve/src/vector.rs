@@ -0,0 +1,115 @@
+use alloc::vec::Vec;
+
+use crate::key::AnimationKey;
+use crate::real::Real;
+
+/// A key of a [`VectorCurve`]: one x shared by `N` channels that each have their own value and tangents
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VectorKey<const N: usize, T = f32> {
+    pub x: T,
+    pub values: [T; N],
+    /// y of each channel's in handle relative to its value. Handle x only matters to the editor.
+    pub tangent_in: [T; N],
+    /// y of each channel's out handle relative to its value
+    pub tangent_out: [T; N],
+}
+
+/// Why channels could not be combined into a vector curve
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ChannelError {
+    /// a channel has no keys
+    Empty,
+    /// the channels have different numbers of keys
+    KeyCount,
+    /// the key at this index is at a different x in some channels
+    KeyTime(usize),
+}
+
+impl core::fmt::Display for ChannelError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ChannelError::Empty => write!(f, "a channel has no keys"),
+            ChannelError::KeyCount => write!(f, "channels have different key counts"),
+            ChannelError::KeyTime(index) => write!(f, "key {index} is at a different time in some channels"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ChannelError {}
+
+/// A curve with `N` channels, like a position or a colour, whose keys share their times. Evaluating finds the segment
+/// once for every channel and returns exactly what `evaluate` returns for each channel on its own.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VectorCurve<const N: usize, T = f32> {
+    keys: Vec<VectorKey<N, T>>,
+}
+
+impl<const N: usize, T: Real> VectorCurve<N, T> {
+    /// combines one curve per channel, which must have keys at the same x values sorted by x
+    pub fn from_channels(channels: [&[AnimationKey<T>]; N]) -> Result<Self, ChannelError> {
+        let Some(first) = channels.first() else {
+            return Ok(Self { keys: Vec::new() });
+        };
+        if channels.iter().any(|channel| channel.is_empty()) {
+            return Err(ChannelError::Empty);
+        }
+        if channels.iter().any(|channel| channel.len() != first.len()) {
+            return Err(ChannelError::KeyCount);
+        }
+
+        let keys = (0..first.len())
+            .map(|index| {
+                let x = first[index].pos.x;
+                if channels.iter().any(|channel| channel[index].pos.x != x) {
+                    return Err(ChannelError::KeyTime(index));
+                }
+                Ok(VectorKey {
+                    x,
+                    values: channels.map(|channel| channel[index].pos.y),
+                    tangent_in: channels.map(|channel| channel[index].tangent_in.y),
+                    tangent_out: channels.map(|channel| channel[index].tangent_out.y),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { keys })
+    }
+
+    pub fn keys(&self) -> &[VectorKey<N, T>] {
+        &self.keys
+    }
+
+    /// the value of every channel at `x`, holding the first and last key values past the ends
+    pub fn evaluate(&self, x: T) -> [T; N] {
+        let (Some(first), Some(last)) = (self.keys.first(), self.keys.last()) else {
+            return [T::ZERO; N];
+        };
+        if x <= first.x {
+            return first.values;
+        }
+        if x >= last.x {
+            return last.values;
+        }
+
+        // the first segment ending at or after `x`, which is the one `evaluate` picks
+        let index = self.keys[1..].partition_point(|key| key.x < x);
+        let (key1, key2) = (&self.keys[index], &self.keys[index + 1]);
+        let delta = key2.x - key1.x;
+        let t = if delta > T::ZERO { (x - key1.x) / delta } else { T::ZERO };
+
+        // the same operations in the same order as `evaluate_pair_bezier`
+        let three = T::from(3.0);
+        let h = T::ONE - t;
+        let a = t * t * t;
+        let b = three * t * t * h;
+        let c = three * t * h * h;
+        let d = h * h * h;
+        core::array::from_fn(|channel| {
+            let p0 = key1.values[channel];
+            let p1 = key1.values[channel] + key1.tangent_out[channel];
+            let p2 = key2.values[channel] + key2.tangent_in[channel];
+            let p3 = key2.values[channel];
+            p3 * a + p2 * b + p1 * c + p0 * d
+        })
+    }
+}
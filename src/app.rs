@@ -1,5 +1,5 @@
 use super::Demo;
-use crate::{curve_editor, syntax_highlighting};
+use crate::{curve_editor, gradient_editor, path_editor, syntax_highlighting};
 use egui::{Context, Ui};
 use egui_notify::Toasts;
 use std::{collections::BTreeSet, time::Duration};
@@ -25,6 +25,8 @@ impl Default for App {
     fn default() -> Self {
         let demos: Vec<Box<dyn Demo>> = vec![
             Box::new(curve_editor::CurveEditor::default()),
+            Box::new(gradient_editor::GradientEditor::default()),
+            Box::new(path_editor::PathEditor::default()),
             Box::new(syntax_highlighting::code_viewer::CodeViewer::default()),
         ];
         let mut open = BTreeSet::new();
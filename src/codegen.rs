@@ -0,0 +1,483 @@
+use std::fmt::Write;
+
+use crate::fitting::Polynomial;
+use crate::gradient::{BlendSpace, ColorStop, Gradient};
+use crate::splines::{self, Path, Precision};
+
+/// Languages code can be generated for
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Language {
+    Rust,
+    C,
+    Glsl,
+    Hlsl,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [Language::Rust, Language::C, Language::Glsl, Language::Hlsl];
+    /// the languages `gradient` supports
+    pub const SHADERS: [Language; 2] = [Language::Glsl, Language::Hlsl];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Rust => "Rust",
+            Language::C => "C",
+            Language::Glsl => "GLSL",
+            Language::Hlsl => "HLSL",
+        }
+    }
+
+    /// the float type of `precision`
+    fn float_type(self, precision: Precision) -> &'static str {
+        match (self, precision) {
+            (Language::Rust, Precision::Single) => "f32",
+            (Language::Rust, Precision::Double) => "f64",
+            (_, Precision::Single) => "float",
+            (_, Precision::Double) => "double",
+        }
+    }
+
+    /// a float literal that the language reads as a float of `precision`
+    pub fn float(self, value: f64, precision: Precision) -> String {
+        // Debug always keeps a decimal point or exponent, which GLSL and HLSL need to not read an int
+        let literal = match precision {
+            Precision::Single => format!("{:?}", value as f32),
+            Precision::Double => format!("{value:?}"),
+        };
+        match (self, precision) {
+            (Language::C, Precision::Single) => format!("{literal}f"),
+            (Language::Glsl, Precision::Double) => format!("{literal}lf"),
+            (Language::Hlsl, Precision::Double) => format!("{literal}L"),
+            _ => literal,
+        }
+    }
+
+    /// `+ value` or `- -value` so negative terms read naturally
+    fn add(self, value: f64, precision: Precision) -> String {
+        if value < 0.0 {
+            format!("- {}", self.float(-value, precision))
+        } else {
+            format!("+ {}", self.float(value, precision))
+        }
+    }
+
+    /// opens a function named `name` taking float `x` and returning a float
+    fn function_header(self, name: &str, precision: Precision) -> String {
+        let float = self.float_type(precision);
+        match self {
+            Language::Rust => format!("fn {name}(x: {float}) -> {float} {{"),
+            Language::C | Language::Glsl | Language::Hlsl => format!("{float} {name}({float} x) {{"),
+        }
+    }
+
+    /// the type of an `n` component vector, an array for Rust and C
+    fn vector_type(self, n: usize, precision: Precision) -> String {
+        match (self, precision) {
+            (Language::Rust, _) => format!("[{}; {n}]", self.float_type(precision)),
+            (Language::C, _) => format!("{}[{n}]", self.float_type(precision)),
+            (Language::Glsl, Precision::Single) => format!("vec{n}"),
+            (Language::Glsl, Precision::Double) => format!("dvec{n}"),
+            (Language::Hlsl, _) => format!("{}{n}", self.float_type(precision)),
+        }
+    }
+
+    /// a vector literal, or an array literal for Rust and C
+    fn vector(self, values: &[f64], precision: Precision) -> String {
+        let values: Vec<String> = values.iter().map(|v| self.float(*v, precision)).collect();
+        match self {
+            Language::Rust => format!("[{}]", values.join(", ")),
+            Language::C => format!("{{{}}}", values.join(", ")),
+            Language::Glsl | Language::Hlsl => {
+                format!("{}({})", self.vector_type(values.len(), precision), values.join(", "))
+            }
+        }
+    }
+
+    /// declares a float local
+    fn local(self, name: &str, value: &str, mutable: bool, precision: Precision) -> String {
+        match self {
+            Language::Rust if mutable => format!("let mut {name} = {value};"),
+            Language::Rust => format!("let {name} = {value};"),
+            Language::C | Language::Glsl | Language::Hlsl => {
+                format!("{} {name} = {value};", self.float_type(precision))
+            }
+        }
+    }
+}
+
+/// `name` as an identifier every language accepts: lowercase with anything but letters and digits replaced by `_`
+pub fn identifier(name: &str) -> String {
+    let mut out: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert_str(0, "curve_");
+    }
+    out
+}
+
+/// A function that evaluates `polynomial` in Horner form, remapping x from the polynomial's domain to -1..1 first.
+/// x outside the domain is not clamped. GLSL needs version 4.0 or `ARB_gpu_shader_fp64` for double precision.
+pub fn polynomial(polynomial: &Polynomial, name: &str, language: Language, precision: Precision) -> String {
+    let (start, end) = (*polynomial.domain.start(), *polynomial.domain.end());
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", language.function_header(name, precision));
+
+    let mut coefficients = polynomial.coefficients.iter().rev();
+    let highest = coefficients.next().copied().unwrap_or_default();
+    if end > start && polynomial.coefficients.len() > 1 {
+        let scale = 2.0 / (end - start);
+        let offset = -(start + end) / (end - start);
+        let u = format!(
+            "x * {} {}",
+            language.float(scale, precision),
+            language.add(offset, precision)
+        );
+        let _ = writeln!(out, "    {}", language.local("u", &u, false, precision));
+        let y = language.float(highest, precision);
+        let _ = writeln!(out, "    {}", language.local("y", &y, true, precision));
+        for c in coefficients {
+            let _ = writeln!(out, "    y = y * u {};", language.add(*c, precision));
+        }
+        let _ = match language {
+            Language::Rust => writeln!(out, "    y"),
+            _ => writeln!(out, "    return y;"),
+        };
+    } else {
+        let _ = match language {
+            Language::Rust => writeln!(out, "    {}", language.float(highest, precision)),
+            _ => writeln!(out, "    return {};", language.float(highest, precision)),
+        };
+    }
+
+    out.push('}');
+    out
+}
+
+/// A function that evaluates one polynomial per channel of a vector curve, sharing the first polynomial's domain.
+/// GLSL returns a `vecN` and HLSL a `floatN`, evaluated together in Horner form. Rust returns an array and C, which
+/// can't return arrays, writes the channels to `out`.
+pub fn vector_polynomial(polynomials: &[Polynomial], name: &str, language: Language, precision: Precision) -> String {
+    let n = polynomials.len();
+    let (start, end) = polynomials
+        .first()
+        .map_or((0.0, 0.0), |p| (*p.domain.start(), *p.domain.end()));
+    let terms = polynomials
+        .iter()
+        .map(|p| p.coefficients.len())
+        .max()
+        .unwrap_or_default();
+    // the coefficients of every channel for each power from the highest down
+    let coefficients: Vec<Vec<f64>> = (0..terms.max(1))
+        .rev()
+        .map(|power| {
+            polynomials
+                .iter()
+                .map(|p| p.coefficients.get(power).copied().unwrap_or_default())
+                .collect()
+        })
+        .collect();
+
+    let mut out = String::new();
+    let float = language.float_type(precision);
+    let vector = language.vector_type(n, precision);
+    let _ = match language {
+        Language::Rust => writeln!(out, "fn {name}(x: {float}) -> {vector} {{"),
+        Language::C => writeln!(out, "void {name}({float} x, {float} out[{n}]) {{"),
+        Language::Glsl | Language::Hlsl => writeln!(out, "{vector} {name}({float} x) {{"),
+    };
+
+    let highest = &coefficients[0];
+    if end > start && terms > 1 {
+        let scale = 2.0 / (end - start);
+        let offset = -(start + end) / (end - start);
+        let u = format!(
+            "x * {} {}",
+            language.float(scale, precision),
+            language.add(offset, precision)
+        );
+        let _ = writeln!(out, "    {}", language.local("u", &u, false, precision));
+        match language {
+            Language::Rust | Language::C => {
+                let y = if language == Language::Rust {
+                    let _ = writeln!(out, "    let mut y = {};", language.vector(highest, precision));
+                    "y"
+                } else {
+                    for (channel, c) in highest.iter().enumerate() {
+                        let _ = writeln!(out, "    out[{channel}] = {};", language.float(*c, precision));
+                    }
+                    "out"
+                };
+                for channel in 0..n {
+                    for c in &coefficients[1..] {
+                        let term = language.add(c[channel], precision);
+                        let _ = writeln!(out, "    {y}[{channel}] = {y}[{channel}] * u {term};");
+                    }
+                }
+                if language == Language::Rust {
+                    let _ = writeln!(out, "    y");
+                }
+            }
+            Language::Glsl | Language::Hlsl => {
+                let _ = writeln!(out, "    {vector} y = {};", language.vector(highest, precision));
+                for c in &coefficients[1..] {
+                    let _ = writeln!(out, "    y = y * u + {};", language.vector(c, precision));
+                }
+                let _ = writeln!(out, "    return y;");
+            }
+        }
+    } else {
+        let _ = match language {
+            Language::Rust => writeln!(out, "    {}", language.vector(highest, precision)),
+            Language::C => {
+                for (channel, c) in highest.iter().enumerate() {
+                    let _ = writeln!(out, "    out[{channel}] = {};", language.float(*c, precision));
+                }
+                Ok(())
+            }
+            Language::Glsl | Language::Hlsl => writeln!(out, "    return {};", language.vector(highest, precision)),
+        };
+    }
+
+    out.push('}');
+    out
+}
+
+/// A shader function named `name` returning the gradient at `t` as linear RGB and unpremultiplied alpha, blending in
+/// the gradient's colour space. `ease` is the gradient's ease curve as a polynomial over 0 - 1, emitted as its own
+/// function before the gradient. Only GLSL and HLSL are supported.
+pub fn gradient(gradient: &Gradient, ease: Option<&Polynomial>, name: &str, language: Language) -> String {
+    let precision = Precision::Single;
+    let vec3 = language.vector_type(3, precision);
+    let vec4 = language.vector_type(4, precision);
+    let mix = match language {
+        Language::Hlsl => "lerp",
+        _ => "mix",
+    };
+    let color = |stop: &ColorStop| {
+        let value = gradient.space.components(stop.color).map(f64::from);
+        language.vector(&value, precision)
+    };
+    let mut out = String::new();
+
+    if let Some(ease) = ease {
+        out += &polynomial(ease, &format!("{name}_ease"), language, precision);
+        out += "\n\n";
+    }
+    let f = |v: f64| language.float(v, precision);
+    match gradient.space {
+        BlendSpace::Srgb => {
+            let _ = writeln!(out, "{vec3} {name}_to_linear({vec3} c) {{");
+            let _ = writeln!(
+                out,
+                "    return {mix}(c / {}, pow((c + {}) / {}, {}), step({}, c));",
+                f(12.92),
+                f(0.055),
+                f(1.055),
+                language.vector(&[2.4; 3], precision),
+                f(0.04045)
+            );
+            out += "}\n\n";
+        }
+        BlendSpace::Linear => {}
+        BlendSpace::Oklab => {
+            let _ = writeln!(out, "{vec3} {name}_to_linear({vec3} lab) {{");
+            let _ = writeln!(out, "    {vec3} lms = {vec3}(");
+            let _ = writeln!(out, "        lab.x + 0.3963377774 * lab.y + 0.2158037573 * lab.z,");
+            let _ = writeln!(out, "        lab.x - 0.1055613458 * lab.y - 0.0638541728 * lab.z,");
+            let _ = writeln!(out, "        lab.x - 0.0894841775 * lab.y - 1.2914855480 * lab.z);");
+            let _ = writeln!(out, "    lms = lms * lms * lms;");
+            let _ = writeln!(out, "    return {vec3}(");
+            let _ = writeln!(
+                out,
+                "        4.0767416621 * lms.x - 3.3077115913 * lms.y + 0.2309699292 * lms.z,"
+            );
+            let _ = writeln!(
+                out,
+                "        -1.2684380046 * lms.x + 2.6097574011 * lms.y - 0.3413193965 * lms.z,"
+            );
+            let _ = writeln!(
+                out,
+                "        -0.0041960863 * lms.x - 0.7034186147 * lms.y + 1.7076642670 * lms.z);"
+            );
+            out += "}\n\n";
+        }
+    }
+
+    let _ = writeln!(out, "{vec4} {name}(float t) {{");
+    match (gradient.stops.first(), gradient.stops.last()) {
+        (Some(first), Some(last)) if gradient.stops.len() > 1 => {
+            let _ = writeln!(out, "    {vec4} c;");
+            let _ = writeln!(out, "    if (t <= {}) {{", f(first.t));
+            let _ = writeln!(out, "        c = {};", color(first));
+            for pair in gradient.stops.windows(2) {
+                let (a, b) = (&pair[0], &pair[1]);
+                if b.t <= a.t {
+                    continue;
+                }
+                let along = if a.t == 0.0 {
+                    format!("t / {}", f(b.t - a.t))
+                } else {
+                    format!("(t {}) / {}", language.add(-a.t, precision), f(b.t - a.t))
+                };
+                let factor = match ease {
+                    Some(_) => format!("{name}_ease({along})"),
+                    None => along,
+                };
+                let _ = writeln!(out, "    }} else if (t < {}) {{", f(b.t));
+                let _ = writeln!(out, "        c = {mix}({}, {}, {factor});", color(a), color(b));
+            }
+            let _ = writeln!(out, "    }} else {{");
+            let _ = writeln!(out, "        c = {};", color(last));
+            let _ = writeln!(out, "    }}");
+        }
+        (Some(only), _) => {
+            let _ = writeln!(out, "    {vec4} c = {};", color(only));
+        }
+        _ => {
+            let _ = writeln!(out, "    {vec4} c = {};", language.vector(&[0.0; 4], precision));
+        }
+    }
+    let _ = match gradient.space {
+        BlendSpace::Linear => writeln!(out, "    return c;"),
+        _ => writeln!(out, "    return {vec4}({name}_to_linear(c.rgb), c.a);"),
+    };
+    out.push('}');
+    out
+}
+
+/// A function returning the point on `path` at `t`, which runs from 0 at the first key to 1 at the end of the path
+/// with every segment taking an equal share, like the path parameter divided by the segment count. t outside 0 - 1
+/// continues the first or last segment. GLSL and HLSL return a `vec2`/`float2`, Rust an array and C writes to `out`.
+pub fn path(path: &Path, name: &str, language: Language, precision: Precision) -> String {
+    let float = language.float_type(precision);
+    let vec2 = language.vector_type(2, precision);
+    let f = |v: f64| language.float(v, precision);
+    let point = |p: splines::Vec2| match language {
+        Language::C => format!("(const {float}[2]){}", language.vector(&[p.x, p.y], precision)),
+        _ => language.vector(&[p.x, p.y], precision),
+    };
+    let mut out = String::new();
+
+    // the cubic Bézier through four control points
+    let (one, three) = (f(1.0), f(3.0));
+    let weights = [
+        "h * h * h".to_owned(),
+        format!("{three} * t * h * h"),
+        format!("{three} * t * t * h"),
+        "t * t * t".to_owned(),
+    ];
+    match language {
+        Language::Rust => {
+            let _ = writeln!(
+                out,
+                "fn {name}_bezier(a: {vec2}, b: {vec2}, c: {vec2}, d: {vec2}, t: {float}) -> {vec2} {{"
+            );
+            let _ = writeln!(out, "    let h = {one} - t;");
+            let _ = writeln!(out, "    let w = [{}];", weights.join(", "));
+            let _ = writeln!(
+                out,
+                "    [0, 1].map(|i| a[i] * w[0] + b[i] * w[1] + c[i] * w[2] + d[i] * w[3])"
+            );
+        }
+        Language::C => {
+            let arg = |name: &str| format!("const {float} {name}[2]");
+            let _ = writeln!(
+                out,
+                "void {name}_bezier({}, {}, {}, {}, {float} t, {float} out[2]) {{",
+                arg("a"),
+                arg("b"),
+                arg("c"),
+                arg("d")
+            );
+            let _ = writeln!(out, "    {float} h = {one} - t;");
+            let _ = writeln!(out, "    for (int i = 0; i < 2; i++) {{");
+            let _ = writeln!(
+                out,
+                "        out[i] = a[i] * ({}) + b[i] * ({}) + c[i] * ({}) + d[i] * ({});",
+                weights[0], weights[1], weights[2], weights[3]
+            );
+            let _ = writeln!(out, "    }}");
+        }
+        Language::Glsl | Language::Hlsl => {
+            let _ = writeln!(
+                out,
+                "{vec2} {name}_bezier({vec2} a, {vec2} b, {vec2} c, {vec2} d, {float} t) {{"
+            );
+            let _ = writeln!(out, "    {float} h = {one} - t;");
+            let _ = writeln!(
+                out,
+                "    return a * ({}) + b * ({}) + c * ({}) + d * ({});",
+                weights[0], weights[1], weights[2], weights[3]
+            );
+        }
+    }
+    out += "}\n\n";
+
+    let _ = match language {
+        Language::Rust => writeln!(out, "fn {name}(t: {float}) -> {vec2} {{"),
+        Language::C => writeln!(out, "void {name}({float} t, {float} out[2]) {{"),
+        Language::Glsl | Language::Hlsl => writeln!(out, "{vec2} {name}({float} t) {{"),
+    };
+    let count = path.segment_count();
+    if count == 0 {
+        let p = path.keys.first().map_or(splines::Vec2::ZERO, |key| key.pos);
+        let _ = match language {
+            Language::Rust => writeln!(out, "    {}", point(p)),
+            Language::C => writeln!(out, "    out[0] = {};\n    out[1] = {};", f(p.x), f(p.y)),
+            Language::Glsl | Language::Hlsl => writeln!(out, "    return {};", point(p)),
+        };
+        out.push('}');
+        return out;
+    }
+
+    let _ = writeln!(
+        out,
+        "    {}",
+        language.local("u", &format!("t * {}", f(count as f64)), false, precision)
+    );
+    for index in 0..count {
+        let (start, end) = path.segment(index);
+        let points = [start.pos, start.tangent_out_world(), end.tangent_in_world(), end.pos].map(point);
+        let along = if index == 0 {
+            "u".to_owned()
+        } else {
+            format!("u - {}", f(index as f64))
+        };
+        let call = match language {
+            Language::C => format!("{name}_bezier({}, {along}, out);", points.join(", ")),
+            _ => format!("{name}_bezier({}, {along})", points.join(", ")),
+        };
+        let last = index + 1 == count;
+        let _ = match (language, last) {
+            (Language::Rust, false) => writeln!(
+                out,
+                "    if u < {} {{\n        return {call};\n    }}",
+                f((index + 1) as f64)
+            ),
+            (Language::Rust, true) => writeln!(out, "    {call}"),
+            (Language::C, false) => writeln!(
+                out,
+                "    if (u < {}) {{\n        {call}\n        return;\n    }}",
+                f((index + 1) as f64)
+            ),
+            (Language::C, true) => writeln!(out, "    {call}"),
+            (_, false) => writeln!(
+                out,
+                "    if (u < {}) {{\n        return {call};\n    }}",
+                f((index + 1) as f64)
+            ),
+            (_, true) => writeln!(out, "    return {call};"),
+        };
+    }
+    out.push('}');
+    out
+}
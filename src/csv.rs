@@ -0,0 +1,261 @@
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+
+use crate::document::NamedCurve;
+use crate::splines::{self, Precision};
+
+/// most rows a sample rate can ask for, which keeps a tiny step over a long domain from stalling the app
+pub const MAX_ROWS: usize = 100_000;
+
+/// A line of CSV that could not be read
+#[derive(Debug, PartialEq, Clone)]
+pub struct CsvError {
+    /// 1 based line number
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for CsvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// Reads `x,y` sample points, one per line, sorted by x. Fields can be separated by commas, semicolons, tabs or spaces
+/// and any columns past the second are ignored. Blank lines, `#` comments and a header on the first line are skipped.
+pub fn parse_samples(text: &str) -> Result<Vec<[f64; 2]>, CsvError> {
+    let mut samples = Vec::new();
+    let mut header_allowed = true;
+
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line
+            .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+            .filter(|field| !field.is_empty());
+        let parsed = match (fields.next(), fields.next()) {
+            (Some(x), Some(y)) => x.parse::<f64>().and_then(|x| Ok([x, y.parse::<f64>()?])),
+            _ => {
+                return Err(CsvError {
+                    line: index + 1,
+                    message: "expected at least two columns".to_owned(),
+                })
+            }
+        };
+
+        match parsed {
+            Ok(sample) if sample.iter().all(|v| v.is_finite()) => samples.push(sample),
+            Ok(_) => {
+                return Err(CsvError {
+                    line: index + 1,
+                    message: "values must be finite".to_owned(),
+                })
+            }
+            Err(_) if header_allowed => {}
+            Err(err) => {
+                return Err(CsvError {
+                    line: index + 1,
+                    message: err.to_string(),
+                })
+            }
+        }
+        header_allowed = false;
+    }
+
+    samples.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    Ok(samples)
+}
+
+/// Character written between fields
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Delimiter {
+    Comma,
+    Semicolon,
+    Tab,
+}
+
+impl Delimiter {
+    pub const ALL: [Delimiter; 3] = [Delimiter::Comma, Delimiter::Semicolon, Delimiter::Tab];
+
+    pub fn as_char(self) -> char {
+        match self {
+            Delimiter::Comma => ',',
+            Delimiter::Semicolon => ';',
+            Delimiter::Tab => '\t',
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Delimiter::Comma => "Comma",
+            Delimiter::Semicolon => "Semicolon",
+            Delimiter::Tab => "Tab",
+        }
+    }
+}
+
+/// Where along x the curve is sampled when exporting
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum SampleRate {
+    /// this many uniform steps, so `steps + 1` rows including both ends
+    Steps(usize),
+    /// a row every `step` x units starting at the beginning of the domain, plus one at the end. The step is widened
+    /// when it would take more than `MAX_ROWS` rows.
+    Step(f64),
+    /// this many points spaced evenly along the length of each curve between its first and last key, including both
+    /// ends
+    EvenLength(usize),
+}
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct ExportOptions {
+    pub rate: SampleRate,
+    pub header: bool,
+    pub delimiter: Delimiter,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            rate: SampleRate::Steps(100),
+            header: true,
+            delimiter: Delimiter::Comma,
+        }
+    }
+}
+
+/// Writes rows of x then the value and dy/dx of every curve across `domain`, evaluated with their modifiers and rounded
+/// to `precision`. Vector curves get a pair of columns per channel. The header names the columns after the
+/// curves, like `size` or `offset.x`.
+///
+/// With `SampleRate::EvenLength` the domain is ignored and the rows are written by `write_even_samples` instead.
+pub fn write_samples(
+    curves: &[NamedCurve],
+    domain: RangeInclusive<f64>,
+    options: &ExportOptions,
+    precision: Precision,
+) -> String {
+    let (start, end) = (*domain.start(), *domain.end());
+    let xs: Vec<f64> = match options.rate {
+        SampleRate::EvenLength(count) => return write_even_samples(curves, count, options, precision),
+        SampleRate::Steps(steps) => {
+            let steps = steps.clamp(1, MAX_ROWS);
+            (0..=steps)
+                .map(|i| start + (end - start) * i as f64 / steps as f64)
+                .collect()
+        }
+        SampleRate::Step(step) if step > 0.0 && end > start => {
+            let step = step.max((end - start) / MAX_ROWS as f64);
+            let count = ((end - start) / step).floor() as usize;
+            let mut xs: Vec<f64> = (0..=count).map(|i| start + step * i as f64).collect();
+            // skip a final step that only differs from the end by rounding
+            if end - xs[count] > step * 1e-3 {
+                xs.push(end);
+            }
+            xs
+        }
+        SampleRate::Step(_) => vec![start],
+    };
+
+    let delimiter = options.delimiter.as_char();
+    let mut out = String::new();
+    let curves = non_empty(curves);
+    if options.header {
+        out.push('x');
+        for curve in &curves {
+            for channel in 0..curve.channels.len() {
+                let name = curve.channel_name(channel);
+                let value = header_field(&name, delimiter);
+                let slope = header_field(&format!("d{name}/dx"), delimiter);
+                let _ = write!(out, "{delimiter}{value}{delimiter}{slope}");
+            }
+        }
+        out.push('\n');
+    }
+    if curves.is_empty() {
+        return out;
+    }
+    for x in xs {
+        out += &precision.format(x);
+        for curve in &curves {
+            for channel in 0..curve.channels.len() {
+                let y = precision.format(curve.evaluate(channel, x));
+                let slope = precision.format(curve.evaluate_derivative(channel, x));
+                let _ = write!(out, "{delimiter}{y}{delimiter}{slope}");
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Writes `count` rows of points spaced evenly along the arc length of every curve, so each row is the same fraction
+/// `s` of the way along every curve rather than at the same x. Each channel gets an x and a y column, like `size x`
+/// and `size y`. Lengths are measured over the polyline `splines::flatten` draws the curve with.
+pub fn write_even_samples(
+    curves: &[NamedCurve],
+    count: usize,
+    options: &ExportOptions,
+    precision: Precision,
+) -> String {
+    let delimiter = options.delimiter.as_char();
+    let mut out = String::new();
+    let curves = non_empty(curves);
+    if options.header {
+        out.push('s');
+        for curve in &curves {
+            for channel in 0..curve.channels.len() {
+                let name = curve.channel_name(channel);
+                let x = header_field(&format!("{name} x"), delimiter);
+                let y = header_field(&format!("{name} y"), delimiter);
+                let _ = write!(out, "{delimiter}{x}{delimiter}{y}");
+            }
+        }
+        out.push('\n');
+    }
+    if curves.is_empty() {
+        return out;
+    }
+
+    let count = count.max(2);
+    let columns: Vec<Vec<splines::Vec2>> = curves
+        .iter()
+        .flat_map(|curve| {
+            curve
+                .channels
+                .iter()
+                .map(|keys| splines::arc_length_table(keys, &curve.modifiers, None).even_points(count))
+        })
+        .collect();
+    for i in 0..count {
+        out += &precision.format(i as f64 / (count - 1) as f64);
+        for points in &columns {
+            let (x, y) = (precision.format(points[i].x), precision.format(points[i].y));
+            let _ = write!(out, "{delimiter}{x}{delimiter}{y}");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// the curves with keys in every channel, the rest can't be sampled
+fn non_empty(curves: &[NamedCurve]) -> Vec<&NamedCurve> {
+    curves
+        .iter()
+        .filter(|curve| curve.channels.iter().all(|keys| !keys.is_empty()))
+        .collect()
+}
+
+/// `name` quoted if it contains anything that would break up the row
+fn header_field(name: &str, delimiter: char) -> String {
+    if name.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    } else {
+        name.to_owned()
+    }
+}
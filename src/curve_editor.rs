@@ -1,335 +1,1843 @@
+use std::sync::Arc;
+
 use egui::plot::{CoordinatesFormatter, PlotBounds, PlotPoint, PlotUi};
 use egui::*;
 use egui_notify::Toasts;
-use plot::{Corner, Line, LineStyle, Plot};
+use plot::{Corner, Legend, Line, LineStyle, Plot, Points};
+
+use curve::{BakedCurve, CurveCursor, WrapMode};
 
-use crate::splines;
+use crate::document::{self, AxisLimits, CurveLimits, KeyConstraints, NamedCurve};
+use crate::expression::Expression;
+use crate::generators::{self, Generator, Motion};
+use crate::presets::{self, Preset};
+use crate::splines::{AnimationKey, ModifierKind};
+use crate::{codegen, csv, fitting, splines};
 
 const POINT_RADIUS: f32 = 5.0;
 const CONTROL_POINT_RADIUS: f32 = 3.0;
-const CIRCLE_CLICK_RADIUS: f32 = 0.03;
+/// extra room around the drawn circles that still counts as a hit. This is in egui points so it grows with
+/// `pixels_per_point` on high DPI displays just like the circles themselves.
+const PICK_TOLERANCE: f32 = 4.0;
 const BOUNDS_OVERSHOOT: f64 = 0.2;
-const TANGENT_LENGTH: f32 = 0.04;
-
-const CURVE_COLOR: Color32 = Color32::LIGHT_BLUE;
+const TANGENT_LENGTH: f64 = 0.04;
+const MIN_AXIS_SPAN: f64 = 0.001;
+/// closest two keys can get along x when keeping keys ordered, as a fraction of the domain
+const MIN_KEY_GAP: f64 = 0.001;
+const SAMPLE_RADIUS: f32 = 2.0;
+/// evenly spaced samples across the domain expressions and modifiers are fitted to and checked at
+const EXPRESSION_SAMPLES: usize = 4096;
+const PRESET_THUMBNAIL_SIZE: f32 = 48.0;
+/// egui memory key of the built in presets, which are fitted the first time they are shown
+const BUILTIN_PRESETS_ID: &str = "curve_editor_builtin_presets";
+/// egui memory key of the presets users saved, which egui persists across sessions
+const USER_PRESETS_ID: &str = "curve_editor_user_presets";
+const PLAYHEAD_RADIUS: f32 = 6.0;
+
+/// colours new curves cycle through
+const CURVE_COLORS: [Color32; 6] = [
+    Color32::LIGHT_BLUE,
+    Color32::from_rgb(255, 160, 60),
+    Color32::from_rgb(200, 120, 255),
+    Color32::from_rgb(90, 220, 200),
+    Color32::from_rgb(255, 110, 170),
+    Color32::from_rgb(230, 230, 110),
+];
 const POINT_COLOR: Color32 = Color32::LIGHT_GREEN;
 const CONTROL_POINT_COLOR: Color32 = Color32::DARK_GREEN;
 const CONTROL_POINT_UNLOCKED_COLOR: Color32 = Color32::GREEN;
 const CONTROL_POINT_LINE_COLOR: Color32 = Color32::LIGHT_GREEN;
 const HOVERED_KEY_STROKE_COLOR: Color32 = Color32::LIGHT_RED;
+const OVERSHOOT_COLOR: Color32 = Color32::RED;
+const PREVIEW_COLOR: Color32 = Color32::GOLD;
+const POLYNOMIAL_COLOR: Color32 = Color32::LIGHT_RED;
+const PLAYHEAD_COLOR: Color32 = Color32::WHITE;
+const SELECTED_KEY_COLOR: Color32 = Color32::WHITE;
+const SAMPLE_COLOR: Color32 = Color32::LIGHT_YELLOW;
+const EXPRESSION_COLOR: Color32 = Color32::KHAKI;
+
+/// moves the part of `key` that `key_field` refers to by `delta`, which is in curve units
+pub fn translate_key(key: &mut AnimationKey, key_field: &AnimationKeyPointField, delta: splines::Vec2) {
+    match key_field {
+        AnimationKeyPointField::Pos => key.pos += delta,
+        AnimationKeyPointField::TanIn => {
+            key.tangent_in += delta;
+            if key.tangent_locked {
+                key.tangent_out -= delta;
+            }
+        }
+        AnimationKeyPointField::TanOut => {
+            key.tangent_out += delta;
+            if key.tangent_locked {
+                key.tangent_in -= delta;
+            }
+        }
+    }
+}
 
-#[derive(Default, PartialEq, Clone)]
-pub struct AnimationKey {
+pub fn toggle_tangent(key: &mut AnimationKey, key_field: AnimationKeyPointField) {
+    if !key.tangent_locked {
+        match key_field {
+            AnimationKeyPointField::TanIn => key.tangent_out = -key.tangent_in,
+            AnimationKeyPointField::TanOut => key.tangent_in = -key.tangent_out,
+            _ => {}
+        }
+    }
+    key.tangent_locked = !key.tangent_locked;
+}
+
+/// a plot coordinate delta as a curve space vector
+pub fn to_curve_vec(v: Vec2) -> splines::Vec2 {
+    curve::vec2(v.x as f64, v.y as f64)
+}
+
+/// a plot coordinate as a curve space position, keeping its full precision
+pub fn from_plot(pt: PlotPoint) -> splines::Vec2 {
+    curve::vec2(pt.x, pt.y)
+}
+
+/// Screen space positions of an `AnimationKey`, in egui points
+#[derive(Default, PartialEq)]
+pub struct AnimationKeyPoint {
     pub pos: Vec2,
     pub tangent_in: Vec2,
     pub tangent_out: Vec2,
-    pub tangent_locked: bool,
 }
 
-impl AnimationKey {
-    fn new(pos: Vec2) -> AnimationKey {
-        AnimationKey {
-            pos,
-            tangent_in: vec2(-TANGENT_LENGTH, 0.0),
-            tangent_out: vec2(TANGENT_LENGTH, 0.0),
-            tangent_locked: true,
+impl AnimationKeyPoint {
+    /// the clickable circles of this key in the order they are drawn, as (field, center, radius)
+    fn handles(&self) -> [(AnimationKeyPointField, Pos2, f32); 3] {
+        [
+            (
+                AnimationKeyPointField::TanIn,
+                self.tangent_in.to_pos2(),
+                CONTROL_POINT_RADIUS,
+            ),
+            (AnimationKeyPointField::Pos, self.pos.to_pos2(), POINT_RADIUS),
+            (
+                AnimationKeyPointField::TanOut,
+                self.tangent_out.to_pos2(),
+                CONTROL_POINT_RADIUS,
+            ),
+        ]
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum AnimationKeyPointField {
+    Pos,
+    TanIn,
+    TanOut,
+}
+
+#[derive(PartialEq)]
+pub struct CurveEditor {
+    /// max error allowed when fitting keys to another curve, in y units
+    fit_tolerance: f64,
+    curve_resolution: f32,
+    dragged_object: Option<(usize, AnimationKeyPointField)>,
+    hovered_object: Option<(usize, AnimationKeyPointField)>,
+    right_click_pos: Option<PlotPoint>,
+    /// id of the key shown in the Key section, the last one clicked
+    selected_key: Option<u64>,
+    /// every curve of the document, drawn together
+    curves: Vec<NamedCurve>,
+    /// index of the curve that receives edits
+    active: usize,
+    points_for_drawing: Vec<AnimationKeyPoint>,
+    preview: Option<Preview>,
+    /// imported (x, y) data drawn behind the curve that keys can be fitted to
+    samples: Vec<[f64; 2]>,
+    /// pasted CSV, which also works on the web where there is no file dialog
+    samples_text: String,
+    /// fit the fewest keys within `fit_tolerance` instead of exactly `sample_fit_keys` keys
+    sample_fit_to_tolerance: bool,
+    sample_fit_keys: usize,
+    /// formula of x that keys can be fitted to
+    expression_text: String,
+    /// draw the expression over the curve
+    show_expression: bool,
+    /// name the active curve is saved as a preset under
+    preset_name: String,
+    export: csv::ExportOptions,
+    polynomial_degree: usize,
+    /// draw the polynomial approximation over the curve
+    show_polynomial: bool,
+    code_language: codegen::Language,
+    /// float width of CSV, key and code exports. Keys are always edited in f64.
+    precision: splines::Precision,
+    /// draw every channel of the active vector curve rather than only the one being edited
+    show_all_channels: bool,
+    /// x of the playhead, which keeps going past the keys for the cursor to wrap
+    playhead: f64,
+    playing: bool,
+    /// domain units per second
+    playback_speed: f64,
+    playback_cursor: CurveCursor,
+    playback_curve: Option<PlaybackCurve>,
+}
+
+/// The result of an operation, shown as a ghost curve until it is applied or cancelled. It is dropped if the keys it
+/// was made from change in the meantime.
+#[derive(PartialEq)]
+struct Preview {
+    source: Vec<AnimationKey>,
+    points: Vec<AnimationKey>,
+}
+
+/// The active channel baked for the playhead to sample through `playback_cursor`. It is baked again when the keys it
+/// was made from change.
+#[derive(PartialEq)]
+struct PlaybackCurve {
+    source: Vec<AnimationKey>,
+    baked: BakedCurve<f64>,
+}
+
+impl Default for CurveEditor {
+    fn default() -> Self {
+        Self {
+            fit_tolerance: 0.001,
+            curve_resolution: 0.05,
+            dragged_object: None,
+            hovered_object: None,
+            right_click_pos: None,
+            selected_key: None,
+            curves: vec![NamedCurve::new(
+                "Curve 1",
+                CURVE_COLORS[0],
+                vec![
+                    AnimationKey::new(curve::vec2(0.0, 0.0), TANGENT_LENGTH),
+                    AnimationKey::new(curve::vec2(0.5, 0.5), TANGENT_LENGTH),
+                    AnimationKey::new(curve::vec2(1.0, 1.0), TANGENT_LENGTH),
+                ],
+            )],
+            active: 0,
+            points_for_drawing: vec![],
+            preview: None,
+            samples: vec![],
+            samples_text: String::new(),
+            sample_fit_to_tolerance: true,
+            sample_fit_keys: 8,
+            expression_text: String::new(),
+            show_expression: true,
+            preset_name: String::new(),
+            export: csv::ExportOptions::default(),
+            polynomial_degree: 5,
+            show_polynomial: false,
+            code_language: codegen::Language::Glsl,
+            precision: splines::Precision::default(),
+            show_all_channels: true,
+            playhead: 0.0,
+            playing: false,
+            playback_speed: 0.5,
+            playback_cursor: CurveCursor::new(WrapMode::Repeat),
+            playback_curve: None,
         }
     }
+}
+
+impl super::Demo for CurveEditor {
+    fn name(&self) -> &'static str {
+        "🗠 Plot"
+    }
+
+    fn show(&mut self, ctx: &Context, open: &mut bool, toasts: &mut Toasts) {
+        Window::new(self.name())
+            .open(open)
+            .default_size(vec2(400.0, 400.0))
+            .min_width(200.)
+            .min_height(300.)
+            // .fixed_size(vec2(400.0, 400.0))
+            // .vscroll(true) // todo: temp fix for debug data on bottom
+            .show(ctx, |ui| self.ui(ui, toasts));
+    }
+}
+
+impl CurveEditor {
+    /// keys of the active channel of the active curve
+    fn points(&self) -> &Vec<AnimationKey> {
+        self.curves[self.active].keys()
+    }
+
+    fn points_mut(&mut self) -> &mut Vec<AnimationKey> {
+        self.curves[self.active].keys_mut()
+    }
+
+    /// domain and range of the active curve
+    fn limits(&self) -> CurveLimits {
+        self.curves[self.active].limits
+    }
 
-    fn tangent_in_screen(&self) -> Pos2 {
-        (self.pos + self.tangent_in).to_pos2()
+    fn limits_mut(&mut self) -> &mut CurveLimits {
+        &mut self.curves[self.active].limits
     }
 
-    fn tangent_out_screen(&self) -> Pos2 {
-        (self.pos + self.tangent_out).to_pos2()
+    /// constraints kept on the keys of the active curve
+    fn constraints(&self) -> KeyConstraints {
+        self.curves[self.active].constraints
     }
 
-    fn intersects(&self, ptr_coord: Pos2) -> Option<AnimationKeyPointField> {
-        let mut nearest_dist = f32::MAX;
-        let mut nearest = None;
+    fn constraints_mut(&mut self) -> &mut KeyConstraints {
+        &mut self.curves[self.active].constraints
+    }
+
+    /// the active curve can only be edited while it is shown and unlocked, and its keys by hand only while they
+    /// aren't generated
+    fn editable(&self) -> bool {
+        let curve = &self.curves[self.active];
+        curve.visible && !curve.locked && curve.generator.is_none()
+    }
 
-        let mut dist = self.pos.to_pos2().distance(ptr_coord);
-        if dist < CIRCLE_CLICK_RADIUS && dist < nearest_dist {
-            nearest_dist = dist;
-            nearest = Some(AnimationKeyPointField::Pos);
+    /// makes the curve at `index` active and applies its constraints, which may have changed since it was last edited
+    fn set_active(&mut self, index: usize) {
+        if index != self.active {
+            self.active = index;
+            self.dragged_object = None;
+            self.hovered_object = None;
+            self.preview = None;
+            if self.editable() {
+                self.for_each_channel(Self::constrain_all_keys);
+            }
         }
+    }
 
-        dist = self.tangent_in_screen().distance(ptr_coord);
-        if dist < CIRCLE_CLICK_RADIUS && dist < nearest_dist {
-            nearest_dist = dist;
-            nearest = Some(AnimationKeyPointField::TanIn);
+    fn set_active_channel(&mut self, channel: usize) {
+        if channel != self.curves[self.active].channel {
+            self.curves[self.active].channel = channel;
+            self.dragged_object = None;
+            self.hovered_object = None;
+            self.preview = None;
         }
+    }
 
-        dist = self.tangent_out_screen().distance(ptr_coord);
-        if dist < CIRCLE_CLICK_RADIUS && dist < nearest_dist {
-            nearest = Some(AnimationKeyPointField::TanOut);
+    /// adds a curve rising across the domain and range of the active curve, with the same limits and constraints, and
+    /// makes it active
+    fn add_curve(&mut self) {
+        let (limits, constraints) = (self.limits(), self.constraints());
+        let (domain, range) = (limits.domain, limits.range);
+        let tangent_length = TANGENT_LENGTH * domain.span();
+        let keys = vec![
+            AnimationKey::new(curve::vec2(domain.min, range.min), tangent_length),
+            AnimationKey::new(curve::vec2(domain.max, range.max), tangent_length),
+        ];
+        let count = self.curves.len();
+        self.curves.push(NamedCurve {
+            limits,
+            constraints,
+            ..NamedCurve::new(
+                format!("Curve {}", count + 1),
+                CURVE_COLORS[count % CURVE_COLORS.len()],
+                keys,
+            )
+        });
+        self.set_active(count);
+        self.constrain_all_keys();
+    }
+
+    fn duplicate_curve(&mut self) {
+        let count = self.curves.len();
+        let mut copy = self.curves[self.active].clone();
+        copy.name += " copy";
+        copy.color = CURVE_COLORS[count % CURVE_COLORS.len()];
+        copy.locked = false;
+        self.curves.push(copy);
+        self.set_active(count);
+    }
+
+    fn remove_curve(&mut self, index: usize) {
+        if self.curves.len() > 1 {
+            self.curves.remove(index);
+            if self.active > index || self.active == self.curves.len() {
+                self.active -= 1;
+            }
+            self.dragged_object = None;
+            self.hovered_object = None;
+            self.preview = None;
         }
-        return nearest;
     }
 
-    fn translate(&mut self, key_field: &AnimationKeyPointField, delta: Vec2) {
-        match key_field {
-            AnimationKeyPointField::Pos => self.pos += delta,
-            AnimationKeyPointField::TanIn => {
-                self.tangent_in += delta;
-                if self.tangent_locked {
-                    self.tangent_out -= delta;
+    /// replaces the keys of the active channel with preset keys, stretching the 0 - 1 square over the domain and range
+    fn insert_preset(&mut self, keys: &[AnimationKey]) {
+        let limits = self.limits();
+        let offset = curve::vec2(limits.domain.min, limits.range.min);
+        let scale = curve::vec2(limits.domain.span(), limits.range.span());
+        *self.points_mut() = keys
+            .iter()
+            .map(|key| {
+                let mut new = AnimationKey::new(key.pos * scale + offset, 0.0);
+                new.tangent_in = key.tangent_in * scale;
+                new.tangent_out = key.tangent_out * scale;
+                new.tangent_locked = key.tangent_locked;
+                new
+            })
+            .collect();
+        self.dragged_object = None;
+        self.hovered_object = None;
+        self.preview = None;
+        self.constrain_all_keys();
+    }
+
+    /// replaces the keys of every channel of the active curve with `motion` across the domain and range
+    fn attach_generator(&mut self, motion: Motion) {
+        let CurveLimits { domain, range } = self.limits();
+        let curve = &mut self.curves[self.active];
+        curve.generator = Some(Generator::new(
+            motion,
+            curve::vec2(domain.min, range.min),
+            curve::vec2(domain.max, range.max),
+        ));
+        curve.generate(self.fit_tolerance);
+        self.dragged_object = None;
+        self.hovered_object = None;
+        self.preview = None;
+    }
+
+    /// the keys of the active channel with the domain and range squeezed into the 0 - 1 square, undoing
+    /// `insert_preset`
+    fn preset_keys(&self) -> Vec<AnimationKey> {
+        let limits = self.limits();
+        let offset = curve::vec2(limits.domain.min, limits.range.min);
+        let scale = curve::vec2(
+            1.0 / limits.domain.span().max(MIN_AXIS_SPAN),
+            1.0 / limits.range.span().max(MIN_AXIS_SPAN),
+        );
+        self.points()
+            .iter()
+            .map(|key| {
+                let mut key = key.clone();
+                key.pos = (key.pos - offset) * scale;
+                key.tangent_in = key.tangent_in * scale;
+                key.tangent_out = key.tangent_out * scale;
+                key
+            })
+            .collect()
+    }
+
+    fn ensure_drawing_points_capacity(&mut self) {
+        if self.points().len() != self.points_for_drawing.len() {
+            self.points_for_drawing
+                .resize_with(self.points().len(), Default::default);
+        }
+    }
+
+    /// Hit tests `ptr_screen` against the keys and tangents as they were last drawn. Nothing is hit while the active
+    /// curve is hidden or locked.
+    fn intersected_key(&self, ptr_screen: Pos2) -> Option<(usize, AnimationKeyPointField)> {
+        if !self.editable() {
+            return None;
+        }
+        pick_handle(&self.points_for_drawing, ptr_screen, false)
+    }
+
+    fn add_key(&mut self, pos: splines::Vec2) {
+        let limits = self.limits();
+        let new_pos = limits.clamp(pos);
+        let tangent_length = TANGENT_LENGTH * limits.domain.span();
+
+        let key = AnimationKey::new(new_pos, tangent_length);
+        let id = key.id;
+        self.points_mut().push(key);
+        self.points_for_drawing.push(AnimationKeyPoint::default());
+        self.sort_keys();
+
+        let index = self.points().iter().position(|e| e.id == id).unwrap();
+        self.constrain_key(index);
+    }
+
+    /// index of the selected key in the active channel
+    fn selected_index(&self) -> Option<usize> {
+        let id = self.selected_key?;
+        self.points().iter().position(|key| key.id == id)
+    }
+
+    fn sort_keys(&mut self) {
+        self.points_mut().sort_by(|a, b| a.pos.x.total_cmp(&b.pos.x));
+    }
+
+    fn update_dragged_object(&mut self, drag_delta: Vec2) {
+        if let Some((index, field)) = self.dragged_object {
+            let key = &mut self.points_mut()[index];
+            // a TCB key that has its tangents dragged becomes a Bézier key
+            if field != AnimationKeyPointField::Pos {
+                key.tcb = None;
+            }
+            translate_key(key, &field, to_curve_vec(drag_delta));
+
+            let mut index = index;
+            if !self.constraints().keep_keys_ordered && field == AnimationKeyPointField::Pos {
+                let id = self.points()[index].id;
+                self.sort_keys();
+                index = self.points().iter().position(|e| e.id == id).unwrap();
+                self.dragged_object = Some((index, field));
+            }
+            self.constrain_key(index);
+        }
+    }
+
+    /// keeps the key at `index` between its neighbours (when keeping keys ordered) and fits the tangents of it and its
+    /// neighbours
+    fn constrain_key(&mut self, index: usize) {
+        if self.constraints().keep_keys_ordered {
+            let gap = self.limits().domain.span() * MIN_KEY_GAP;
+            let min_x = index.checked_sub(1).map_or(f64::MIN, |i| self.points()[i].pos.x + gap);
+            let max_x = self.points().get(index + 1).map_or(f64::MAX, |p| p.pos.x - gap);
+            if min_x <= max_x {
+                let pos = &mut self.points_mut()[index].pos;
+                pos.x = pos.x.clamp(min_x, max_x);
+            }
+        }
+
+        splines::apply_tcb(self.points_mut());
+        for i in index.saturating_sub(1)..(index + 2).min(self.points().len()) {
+            self.fit_tangents(i);
+        }
+        if self.constraints().monotonic {
+            splines::make_monotonic(self.points_mut());
+        }
+    }
+
+    /// derives the tangents of TCB keys and then fits every tangent, so the constraints win over the TCB controls
+    fn constrain_all_keys(&mut self) {
+        self.sort_keys();
+        splines::apply_tcb(self.points_mut());
+        for i in 0..self.points().len() {
+            self.fit_tangents(i);
+        }
+        if self.constraints().monotonic {
+            splines::make_monotonic(self.points_mut());
+        }
+    }
+
+    /// replaces the curve with its inverse, swapping the domain and range
+    fn invert(&mut self, toasts: &mut Toasts) {
+        match fitting::invert(self.points(), self.fit_tolerance) {
+            Ok(points) => {
+                *self.points_mut() = points;
+                let limits = self.limits_mut();
+                std::mem::swap(&mut limits.domain, &mut limits.range);
+                self.constrain_all_keys();
+                toasts.info(format!("curve inverted with {} keys", self.points().len()));
+            }
+            Err(err) => {
+                toasts.error(format!("cannot invert: {err}"));
+            }
+        }
+    }
+
+    fn preview_reduced_keys(&mut self) {
+        self.preview = Some(Preview {
+            source: self.points().clone(),
+            points: fitting::reduce_keys(self.points(), self.fit_tolerance, self.constraints().monotonic),
+        });
+    }
+
+    fn apply_preview(&mut self, toasts: &mut Toasts) {
+        if let Some(preview) = self.preview.take() {
+            toasts.info(format!(
+                "{} keys replaced with {}",
+                self.points().len(),
+                preview.points.len()
+            ));
+            let limits = self.limits();
+            *self.points_mut() = preview
+                .points
+                .into_iter()
+                .map(|mut key| {
+                    key.pos = limits.clamp(key.pos);
+                    key
+                })
+                .collect();
+            self.constrain_all_keys();
+        }
+    }
+
+    fn load_samples(&mut self, text: &str, toasts: &mut Toasts) {
+        match csv::parse_samples(text) {
+            Ok(samples) if samples.is_empty() => {
+                toasts.error("no samples found");
+            }
+            Ok(samples) => {
+                toasts.info(format!("{} samples loaded", samples.len()));
+                self.samples = samples;
+            }
+            Err(err) => {
+                toasts.error(format!("cannot read samples: {err}"));
+            }
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn import_samples(&mut self, toasts: &mut Toasts) {
+        if let Some(path) = rfd::FileDialog::new().add_filter("CSV", &["csv", "txt"]).pick_file() {
+            match std::fs::read_to_string(&path) {
+                Ok(text) => self.load_samples(&text, toasts),
+                Err(err) => {
+                    toasts.error(format!("cannot read {}: {err}", path.display()));
                 }
             }
-            AnimationKeyPointField::TanOut => {
-                self.tangent_out += delta;
-                if self.tangent_locked {
-                    self.tangent_in -= delta;
+        }
+    }
+
+    fn preview_sample_fit(&mut self, toasts: &mut Toasts) {
+        let points = if self.sample_fit_to_tolerance {
+            fitting::fit_samples_to_tolerance(&self.samples, self.fit_tolerance, fitting::MAX_SAMPLE_FIT_KEYS)
+        } else {
+            fitting::fit_samples(&self.samples, self.sample_fit_keys)
+        };
+        match points {
+            Ok(points) => {
+                self.preview = Some(Preview {
+                    source: self.points().clone(),
+                    points,
+                });
+            }
+            Err(err) => {
+                toasts.error(format!("cannot fit the samples: {err}"));
+            }
+        }
+    }
+
+    /// evenly spaced x across the domain, or across all keys when the domain is unclamped, for fitting keys to
+    fn domain_samples(&self) -> Vec<f64> {
+        let (min, max) = self.limits().domain.extents(self.points().iter().map(|p| p.pos.x));
+        (0..=EXPRESSION_SAMPLES)
+            .map(|i| min + (max - min) * i as f64 / EXPRESSION_SAMPLES as f64)
+            .collect()
+    }
+
+    /// previews the fewest keys within the fit tolerance of `expression` across the domain, which fails where the
+    /// expression is undefined
+    fn preview_expression_fit(&mut self, expression: &Expression) -> Result<(), String> {
+        let samples = self.domain_samples();
+        if let Some(x) = samples.iter().find(|&&x| !expression.evaluate(x).is_finite()) {
+            return Err(format!("the expression is undefined at x = {x:.5}"));
+        }
+        let points = fitting::fit_function(
+            |x| expression.evaluate(x),
+            &samples,
+            self.fit_tolerance,
+            self.constraints().monotonic,
+        );
+        self.preview = Some(Preview {
+            source: self.points().clone(),
+            points,
+        });
+        Ok(())
+    }
+
+    /// modifiers with settings that show what they do on a curve in the current limits
+    fn new_modifiers(&self) -> [splines::Modifier; 5] {
+        let CurveLimits { domain, range } = self.limits();
+        [
+            ModifierKind::Cycles {
+                before: splines::WrapMode::Repeat,
+                after: splines::WrapMode::Repeat,
+            },
+            ModifierKind::Noise {
+                seed: 0,
+                amplitude: range.span() * 0.05,
+                frequency: 10.0 / domain.span().max(MIN_AXIS_SPAN),
+            },
+            ModifierKind::Envelope {
+                reference: range.min,
+                points: vec![curve::vec2(domain.min, 1.0), curve::vec2(domain.max, 0.0)],
+            },
+            ModifierKind::Stepped {
+                step: domain.span() / 10.0,
+                offset: domain.min,
+            },
+            ModifierKind::Limits {
+                min: Some(range.min),
+                max: Some(range.max),
+            },
+        ]
+        .map(splines::Modifier::new)
+    }
+
+    /// replaces the keys of every channel of the active curve with keys fitted to its modifiers across the domain
+    fn bake_modifiers(&mut self, toasts: &mut Toasts) {
+        let samples = self.domain_samples();
+        let (tolerance, monotonic) = (self.fit_tolerance, self.constraints().monotonic);
+        let curve = &mut self.curves[self.active];
+        let count = curve.modifiers.len();
+        curve.bake_modifiers(&samples, tolerance, monotonic);
+        self.dragged_object = None;
+        self.hovered_object = None;
+        self.preview = None;
+        toasts.info(format!("baked {count} modifiers into {} keys", self.points().len()));
+    }
+
+    /// every curve sampled across the domain, or across all keys when the domain is unclamped
+    fn export_csv(&self) -> String {
+        let xs = self
+            .curves
+            .iter()
+            .flat_map(|curve| curve.keys().iter().map(|p| p.pos.x));
+        let (min, max) = self.limits().domain.extents(xs);
+        csv::write_samples(&self.curves, min..=max, &self.export, self.precision)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_csv(&self, toasts: &mut Toasts) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("curve.csv")
+            .save_file()
+        {
+            match std::fs::write(&path, self.export_csv()) {
+                Ok(()) => {
+                    toasts.info(format!("saved {}", path.display()));
+                }
+                Err(err) => {
+                    toasts.error(format!("cannot write {}: {err}", path.display()));
                 }
             }
         }
     }
 
-    fn toggle_tangent(&mut self, key_field: AnimationKeyPointField) {
-        if !self.tangent_locked {
-            match key_field {
-                AnimationKeyPointField::TanIn => self.tangent_out = -self.tangent_in,
-                AnimationKeyPointField::TanOut => self.tangent_in = -self.tangent_out,
-                _ => {}
+    /// the curves in RON at the export precision, which runtimes can load with the `curve` crate's `serde` feature
+    fn curves_ron(&self) -> String {
+        document::to_ron(&self.curves, self.precision)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_curves(&self, toasts: &mut Toasts) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("RON", &["ron"])
+            .set_file_name("curves.ron")
+            .save_file()
+        {
+            match std::fs::write(&path, self.curves_ron()) {
+                Ok(()) => {
+                    toasts.info(format!("saved {}", path.display()));
+                }
+                Err(err) => {
+                    toasts.error(format!("cannot write {}: {err}", path.display()));
+                }
             }
         }
-        self.tangent_locked = !self.tangent_locked;
     }
 
-    pub fn tangent_in_world(&self) -> Vec2 {
-        self.pos + self.tangent_in
+    #[cfg(not(target_arch = "wasm32"))]
+    fn open_curves(&mut self, toasts: &mut Toasts) {
+        let Some(path) = rfd::FileDialog::new().add_filter("RON", &["ron"]).pick_file() else {
+            return;
+        };
+        let curves = std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| document::from_ron(&text).map_err(|err| err.to_string()));
+        match curves {
+            Ok(curves) if curves.is_empty() || curves.iter().any(|curve| curve.channels.iter().any(Vec::is_empty)) => {
+                toasts.error(format!("{} has a curve without keys", path.display()));
+            }
+            Ok(curves) => {
+                self.curves = curves;
+                self.active = 0;
+                self.dragged_object = None;
+                self.hovered_object = None;
+                self.preview = None;
+                toasts.info(format!("opened {}", path.display()));
+            }
+            Err(err) => {
+                toasts.error(format!("cannot read {}: {err}", path.display()));
+            }
+        }
     }
 
-    pub fn tangent_out_world(&self) -> Vec2 {
-        self.pos + self.tangent_out
+    fn fit_polynomial(&self, keys: &[AnimationKey], domain: &AxisLimits) -> fitting::Polynomial {
+        let (min, max) = domain.extents(keys.iter().map(|p| p.pos.x));
+        fitting::fit_polynomial(keys, min..=max, self.polynomial_degree)
     }
-}
 
-impl Into<AnimationKeyPoint> for &AnimationKey {
-    fn into(self) -> AnimationKeyPoint {
-        AnimationKeyPoint {
-            pos: self.pos,
-            tangent_in: self.tangent_in,
-            tangent_out: self.tangent_out,
+    /// a polynomial function for every curve, named after the curve. Vector curves return every channel.
+    fn polynomial_code(&self) -> String {
+        self.curves
+            .iter()
+            .map(|curve| {
+                let name = codegen::identifier(&curve.name);
+                if curve.is_vector() {
+                    let polynomials: Vec<_> = curve
+                        .channels
+                        .iter()
+                        .map(|keys| self.fit_polynomial(keys, &curve.limits.domain))
+                        .collect();
+                    codegen::vector_polynomial(&polynomials, &name, self.code_language, self.precision)
+                } else {
+                    let polynomial = self.fit_polynomial(curve.keys(), &curve.limits.domain);
+                    codegen::polynomial(&polynomial, &name, self.code_language, self.precision)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn make_monotonic(&mut self, toasts: &mut Toasts) {
+        splines::make_monotonic(self.points_mut());
+        if splines::keys_are_monotonic(self.points()) {
+            toasts.info("curve made monotonic");
+        } else {
+            toasts.warning("key values change direction so only each segment is monotonic");
         }
     }
-}
 
-#[derive(Default, PartialEq)]
-struct AnimationKeyPoint {
-    pos: Vec2,
-    tangent_in: Vec2,
-    tangent_out: Vec2,
-}
+    /// shortens the tangents of the key at `index` so their x extents stay within the neighbouring segments (when
+    /// keeping keys ordered) and their y extents stay inside the range (when preventing overshoot). Since a Bézier
+    /// segment never leaves the hull of its control points the evaluated curve then stays inside as well.
+    fn fit_tangents(&mut self, index: usize) {
+        let prev_x = index.checked_sub(1).map(|i| self.points()[i].pos.x);
+        let next_x = self.points().get(index + 1).map(|p| p.pos.x);
+        let constraints = self.constraints();
+        let keep_keys_ordered = constraints.keep_keys_ordered;
+        let range = self.limits().range;
+        let prevent_overshoot = constraints.prevent_overshoot && range.clamped;
+
+        let pt = &mut self.points_mut()[index];
+        if keep_keys_ordered {
+            if let Some(prev_x) = prev_x {
+                pt.tangent_in = fit_tangent(pt.tangent_in, 0, (prev_x - pt.pos.x).min(0.0), 0.0);
+            }
+            if let Some(next_x) = next_x {
+                pt.tangent_out = fit_tangent(pt.tangent_out, 0, 0.0, (next_x - pt.pos.x).max(0.0));
+            }
+        }
 
-#[derive(PartialEq, Debug, Clone, Copy)]
-enum AnimationKeyPointField {
-    Pos,
-    TanIn,
-    TanOut,
-}
+        if prevent_overshoot {
+            let min_y = (range.min - pt.pos.y).min(0.0);
+            let max_y = (range.max - pt.pos.y).max(0.0);
+            pt.tangent_in = fit_tangent(pt.tangent_in, 1, min_y, max_y);
+            pt.tangent_out = fit_tangent(pt.tangent_out, 1, min_y, max_y);
+        }
+    }
 
-#[derive(PartialEq)]
-pub struct CurveEditor {
-    constrain_to_01: bool,
-    curve_resolution: f32,
-    dragged_object: Option<(usize, AnimationKeyPointField)>,
-    hovered_object: Option<(usize, AnimationKeyPointField)>,
-    right_click_pos: Option<Pos2>,
-    points: Vec<AnimationKey>,
-    points_for_drawing: Vec<AnimationKeyPoint>,
-}
+    /// runs `f` with each channel of the active curve active in turn
+    fn for_each_channel(&mut self, mut f: impl FnMut(&mut Self)) {
+        let channel = self.curves[self.active].channel;
+        for c in 0..self.curves[self.active].channels.len() {
+            self.curves[self.active].channel = c;
+            f(self);
+        }
+        self.curves[self.active].channel = channel;
+    }
 
-impl Default for CurveEditor {
-    fn default() -> Self {
-        Self {
-            constrain_to_01: false,
-            curve_resolution: 0.05,
-            dragged_object: None,
-            hovered_object: None,
-            right_click_pos: None,
-            points: vec![
-                AnimationKey::new(vec2(0.0, 0.0)),
-                AnimationKey::new(vec2(0.5, 0.5)),
-                AnimationKey::new(vec2(1.0, 1.0)),
-            ],
-            points_for_drawing: vec![],
+    /// moves the keys of every channel of the active curve inside its limits
+    fn clamp_keys(&mut self) {
+        let limits = self.limits();
+        self.for_each_channel(|editor| {
+            for pt in editor.points_mut() {
+                pt.pos = limits.clamp(pt.pos);
+            }
+            editor.constrain_all_keys();
+        });
+    }
+
+    /// remaps the keys of the active curve so its domain and range become 0 - 1. Unclamped axes are remapped from the
+    /// extents of the keys.
+    fn normalize_keys(&mut self) {
+        let axis_extents = |limits: &AxisLimits, values: Vec<f64>| {
+            let (min, max) = if limits.clamped {
+                (limits.min, limits.max)
+            } else {
+                values
+                    .into_iter()
+                    .fold((f64::MAX, f64::MIN), |(min, max), v| (min.min(v), max.max(v)))
+            };
+            (min, (max - min).max(MIN_AXIS_SPAN))
+        };
+        let curve = &mut self.curves[self.active];
+        let keys = || curve.channels.iter().flatten();
+        let (x_min, x_span) = axis_extents(&curve.limits.domain, keys().map(|p| p.pos.x).collect());
+        let (y_min, y_span) = axis_extents(&curve.limits.range, keys().map(|p| p.pos.y).collect());
+
+        let offset = curve::vec2(x_min, y_min);
+        let scale = curve::vec2(1.0 / x_span, 1.0 / y_span);
+        for pt in curve.channels.iter_mut().flatten() {
+            pt.pos = (pt.pos - offset) * scale;
+            pt.tangent_in = pt.tangent_in * scale;
+            pt.tangent_out = pt.tangent_out * scale;
         }
+
+        curve.limits.domain.min = 0.0;
+        curve.limits.domain.max = 1.0;
+        curve.limits.range.min = 0.0;
+        curve.limits.range.max = 1.0;
     }
-}
 
-impl super::Demo for CurveEditor {
-    fn name(&self) -> &'static str {
-        "🗠 Plot"
+    fn plot_bounds(&self) -> PlotBounds {
+        let keys = || {
+            self.curves
+                .iter()
+                .filter(|curve| curve.visible)
+                .flat_map(|curve| curve.channels.iter().flatten())
+        };
+        let xs = keys().map(|p| p.pos.x).chain(self.samples.iter().map(|s| s[0]));
+        let ys = keys().map(|p| p.pos.y).chain(self.samples.iter().map(|s| s[1]));
+        let (x_min, x_max) = self.limits().domain.extents(xs);
+        let (y_min, y_max) = self.limits().range.extents(ys);
+        let x_overshoot = (x_max - x_min) * BOUNDS_OVERSHOOT;
+        let y_overshoot = (y_max - y_min) * BOUNDS_OVERSHOOT;
+
+        PlotBounds::from_min_max(
+            [x_min - x_overshoot, y_min - y_overshoot],
+            [x_max + x_overshoot, y_max + y_overshoot],
+        )
+    }
+
+    /// every visible curve, named for the legend. The active channel of the active curve is drawn last so it sits on
+    /// top. The other channels of the active curve are hidden unless `show_all_channels` is set.
+    fn draw_curves(&self, plot_ui: &mut PlotUi) {
+        let active = (self.active, self.curves[self.active].channel);
+        let channels = self
+            .curves
+            .iter()
+            .enumerate()
+            .filter(|(_, curve)| curve.visible)
+            .flat_map(|(i, curve)| (0..curve.channels.len()).map(move |c| (i, c)))
+            .filter(|&(i, c)| (i, c) != active && (i != self.active || self.show_all_channels));
+        let bounds = plot_ui.plot_bounds();
+        for (i, c) in channels.chain([active]) {
+            let curve = &self.curves[i];
+            if curve.visible && !curve.channels[c].is_empty() {
+                let pts = splines::flatten_modified(
+                    &curve.channels[c],
+                    &curve.modifiers,
+                    bounds.min()[0]..=bounds.max()[0],
+                    Some(self.curve_resolution),
+                );
+                let width = if (i, c) == active { 2.0 } else { 1.0 };
+                plot_ui.line(
+                    Line::new(pts)
+                        .color(channel_color(curve.color, c))
+                        .width(width)
+                        .style(LineStyle::Solid)
+                        .name(curve.channel_name(c)),
+                );
+            }
+        }
+    }
+
+    /// segments whose evaluated curve leaves the range, drawn on top of the curve to highlight them
+    fn draw_overshooting_segments(&self, plot_ui: &mut PlotUi) {
+        let range = self.limits().range;
+        if !range.clamped {
+            return;
+        }
+
+        // ignore float noise from segments that just touch the range
+        let epsilon = range.span() * 1e-4;
+        for (i, chunk) in self.points().windows(2).enumerate() {
+            let (min, max) = splines::segment_y_extents(&chunk[0], &chunk[1]);
+            if min < range.min - epsilon || max > range.max + epsilon {
+                let pts = splines::flatten(&self.points()[i..=i + 1], Some(self.curve_resolution));
+                plot_ui.line(Line::new(pts).color(OVERSHOOT_COLOR).width(2.0));
+            }
+        }
+    }
+
+    fn draw_preview(&self, plot_ui: &mut PlotUi) {
+        if let Some(preview) = &self.preview {
+            let pts = splines::flatten(&preview.points, Some(self.curve_resolution));
+            plot_ui.line(Line::new(pts).color(PREVIEW_COLOR).style(LineStyle::dashed_loose()));
+        }
+    }
+
+    fn draw_samples(&self, plot_ui: &mut PlotUi) {
+        if !self.samples.is_empty() {
+            plot_ui.points(
+                Points::new(self.samples.clone())
+                    .radius(SAMPLE_RADIUS)
+                    .color(SAMPLE_COLOR),
+            );
+        }
+    }
+
+    /// Where the playhead lands after wrapping, the active channel there with the modifiers applied and the offset
+    /// the wrap mode adds on top of it. The channel is baked again only when its keys change, and sampled through the
+    /// cursor so playback steps from segment to segment.
+    fn sample_playhead(&mut self) -> Option<(f64, f64, f64)> {
+        if self.points().is_empty() {
+            self.playback_curve = None;
+            return None;
+        }
+        if self
+            .playback_curve
+            .as_ref()
+            .map_or(true, |curve| curve.source != *self.points())
+        {
+            self.playback_curve = Some(PlaybackCurve {
+                source: self.points().clone(),
+                baked: BakedCurve::new(self.points()),
+            });
+            // the cursor remembers a segment of the old curve
+            self.playback_cursor = CurveCursor::new(self.playback_cursor.wrap);
+        }
+
+        let baked = &self.playback_curve.as_ref()?.baked;
+        let value = self.playback_cursor.sample(baked, self.playhead);
+        let (x, offset) = self.playback_cursor.wrap_x(baked, self.playhead);
+        let active = &self.curves[self.active];
+        let y = if active.has_modifiers() {
+            active.evaluate(active.channel, x)
+        } else {
+            value - offset
+        };
+        Some((x, y, offset))
+    }
+
+    /// marks where the playhead lands on the curve after wrapping
+    fn draw_playhead(&self, plot_ui: &mut PlotUi, playhead: Option<(f64, f64, f64)>) {
+        if let Some((x, y, _)) = playhead {
+            plot_ui.points(Points::new(vec![[x, y]]).radius(PLAYHEAD_RADIUS).color(PLAYHEAD_COLOR));
+        }
+    }
+
+    fn draw_polynomial(&self, plot_ui: &mut PlotUi) {
+        if self.show_polynomial {
+            let polynomial = self.fit_polynomial(self.points(), &self.limits().domain);
+            let (start, end) = (*polynomial.domain.start(), *polynomial.domain.end());
+            let steps = (((end - start) / self.curve_resolution as f64).ceil() as usize).clamp(1, 4096);
+            let pts: Vec<[f64; 2]> = (0..=steps)
+                .map(|i| {
+                    let x = start + (end - start) * i as f64 / steps as f64;
+                    [x, polynomial.evaluate(x)]
+                })
+                .collect();
+            plot_ui.line(Line::new(pts).color(POLYNOMIAL_COLOR).style(LineStyle::dashed_dense()));
+        }
+    }
+
+    /// the expression across the domain, leaving out where it is undefined
+    fn draw_expression(&self, plot_ui: &mut PlotUi) {
+        if !self.show_expression {
+            return;
+        }
+        if let Ok(expression) = Expression::parse(&self.expression_text) {
+            let pts: Vec<[f64; 2]> = self
+                .domain_samples()
+                .into_iter()
+                .map(|x| [x, expression.evaluate(x)])
+                .filter(|pt| pt[1].is_finite())
+                .collect();
+            plot_ui.line(Line::new(pts).color(EXPRESSION_COLOR).style(LineStyle::dashed_dense()));
+        }
+    }
+
+    fn draw_curve_no_tolerance(&self) -> Line {
+        let orig_pts = splines::flatten(self.points(), None);
+        Line::new(orig_pts)
+            .color(Color32::from_rgba_premultiplied(0, 200, 0, 8))
+            .style(LineStyle::Solid)
+    }
+
+    fn draw_tangent_lines(&self, plot_ui: &mut PlotUi) {
+        for (i, pt) in self.points().iter().enumerate() {
+            let mut pts = Vec::with_capacity(3);
+
+            if i > 0 {
+                pts.push(pt.tangent_in_world().into());
+            }
+            pts.push(pt.pos.into());
+
+            if i < self.points().len() - 1 {
+                pts.push(pt.tangent_out_world().into());
+            }
+
+            plot_ui.line(Line::new(pts).color(CONTROL_POINT_LINE_COLOR));
+        }
+    }
+
+    fn draw_keys(&self, painter: Painter) {
+        draw_handles(
+            &painter,
+            &self.points_for_drawing,
+            self.points(),
+            self.hovered_object,
+            false,
+        );
+        if let Some(index) = self.selected_index() {
+            painter.circle_stroke(
+                self.points_for_drawing[index].pos.to_pos2(),
+                POINT_RADIUS + 2.0,
+                Stroke::new(1.5, SELECTED_KEY_COLOR),
+            );
+        }
     }
+}
+
+impl CurveEditor {
+    fn ui(&mut self, ui: &mut Ui, toasts: &mut Toasts) -> Response {
+        ui.horizontal(|ui| {
+            ui.collapsing("Instructions", |ui| {
+                ui.label("Command/Ctrl click tangent to toggle tangent lock (or right-click for menu).");
+                ui.label("Alt click key to delete (or right click for menu).");
+                ui.label("Alt click empty space to add a key (or right click for menu).");
+                ui.label("Click a key to show it in the Key section.");
+            });
+        });
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.style_mut().wrap = Some(false);
+            // the constraints reshape the keys of the active curve, so they can't be changed while it is locked
+            ui.add_enabled_ui(self.editable(), |ui| {
+                if ui
+                    .toggle_value(&mut self.constraints_mut().keep_keys_ordered, "Keep Keys Ordered")
+                    .on_hover_text("Stop keys from passing their neighbours and tangents from leaving their segment")
+                    .changed()
+                    && self.constraints().keep_keys_ordered
+                {
+                    self.for_each_channel(Self::constrain_all_keys);
+                }
+
+                if ui
+                    .add_enabled(
+                        self.limits().range.clamped,
+                        SelectableLabel::new(self.constraints().prevent_overshoot, "Prevent Overshoot"),
+                    )
+                    .on_hover_text("Fit tangents so the curve never leaves the range")
+                    .clicked()
+                {
+                    let constraints = self.constraints_mut();
+                    constraints.prevent_overshoot = !constraints.prevent_overshoot;
+                    self.for_each_channel(Self::constrain_all_keys);
+                }
+            });
+
+            ui.add(
+                egui::Slider::new(&mut self.curve_resolution, 0.0001..=0.1)
+                    .logarithmic(true)
+                    .text("Curve Tolerance"),
+            );
+
+            ComboBox::from_id_source("export_precision")
+                .selected_text(self.precision.name())
+                .show_ui(ui, |ui| {
+                    for precision in splines::Precision::ALL {
+                        ui.selectable_value(&mut self.precision, precision, precision.name());
+                    }
+                })
+                .response
+                .on_hover_text("Float width of CSV, key and code exports");
+
+            egui::reset_button(ui, self);
+        });
+
+        ui.horizontal(|ui| {
+            // everything here edits the active curve's keys
+            ui.set_enabled(self.editable());
+            if ui
+                .toggle_value(&mut self.constraints_mut().monotonic, "Monotonic")
+                .on_hover_text("Adjust tangents whenever keys move so the curve never changes direction")
+                .changed()
+                && self.constraints().monotonic
+            {
+                self.constrain_all_keys();
+            }
+            if ui.button("Make Monotonic").clicked() {
+                self.make_monotonic(toasts);
+            }
+            if ui
+                .button("Invert")
+                .on_hover_text("Replace the curve with its inverse, mapping values back to times")
+                .clicked()
+            {
+                self.invert(toasts);
+            }
+            if ui
+                .button("Reduce Keys")
+                .on_hover_text("Preview the fewest keys that stay within the fit tolerance of the curve")
+                .clicked()
+            {
+                self.preview_reduced_keys();
+            }
+            ui.add(
+                DragValue::new(&mut self.fit_tolerance)
+                    .speed(0.0001)
+                    .clamp_range(0.00001..=1.0)
+                    .prefix("Fit Tolerance: "),
+            );
+
+            if let Some(count) = self.preview.as_ref().map(|preview| preview.points.len()) {
+                if ui
+                    .button(format!("Apply ({} → {count} keys)", self.points().len()))
+                    .clicked()
+                {
+                    self.apply_preview(toasts);
+                }
+                if ui.button("Cancel").clicked() {
+                    self.preview = None;
+                }
+            }
+            if self.constraints().monotonic && !splines::keys_are_monotonic(self.points()) {
+                ui.colored_label(ui.visuals().warn_fg_color, "⚠ key values change direction");
+            }
+        });
+
+        CollapsingHeader::new("Curves").default_open(true).show(ui, |ui| {
+            let mut activate = None;
+            let mut remove = None;
+            let mut resize = None;
+            Grid::new("curves").num_columns(7).show(ui, |ui| {
+                let removable = self.curves.len() > 1;
+                for (i, curve) in self.curves.iter_mut().enumerate() {
+                    if ui
+                        .radio(i == self.active, "")
+                        .on_hover_text("Edit this curve")
+                        .clicked()
+                    {
+                        activate = Some(i);
+                    }
+                    ui.add(TextEdit::singleline(&mut curve.name).desired_width(100.0));
+                    ui.color_edit_button_srgba(&mut curve.color);
+                    let mut count = curve.channels.len();
+                    ComboBox::from_id_source(("curve_dimension", i))
+                        .width(60.0)
+                        .selected_text(document::DIMENSION_NAMES[count - 1])
+                        .show_ui(ui, |ui| {
+                            for (n, name) in document::DIMENSION_NAMES.iter().enumerate() {
+                                ui.selectable_value(&mut count, n + 1, *name);
+                            }
+                        })
+                        .response
+                        .on_hover_text("Output one value or a vector whose channels share their key times");
+                    if count != curve.channels.len() {
+                        resize = Some((i, count));
+                    }
+                    ui.toggle_value(&mut curve.visible, "👁").on_hover_text("Show the curve");
+                    ui.toggle_value(&mut curve.locked, "🔒")
+                        .on_hover_text("Stop the keys from being edited");
+                    if ui
+                        .add_enabled(removable, Button::new("🗑"))
+                        .on_hover_text("Remove the curve")
+                        .clicked()
+                    {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(index) = activate {
+                self.set_active(index);
+            }
+            if let Some((index, count)) = resize {
+                self.set_active(index);
+                self.curves[index].set_channel_count(count);
+                self.dragged_object = None;
+                self.preview = None;
+            }
+            if let Some(index) = remove {
+                self.remove_curve(index);
+                toasts.info("curve removed");
+            }
+
+            if self.curves[self.active].is_vector() {
+                ui.horizontal(|ui| {
+                    ui.label("Channel");
+                    let mut channel = self.curves[self.active].channel;
+                    for (c, name) in document::CHANNEL_NAMES[..self.curves[self.active].channels.len()]
+                        .iter()
+                        .enumerate()
+                    {
+                        ui.selectable_value(&mut channel, c, *name);
+                    }
+                    self.set_active_channel(channel);
+                    ui.checkbox(&mut self.show_all_channels, "Show All")
+                        .on_hover_text("Draw every channel of the curve, not only the one being edited");
+                });
+            }
 
-    fn show(&mut self, ctx: &Context, open: &mut bool, toasts: &mut Toasts) {
-        Window::new(self.name())
-            .open(open)
-            .default_size(vec2(400.0, 400.0))
-            .min_width(200.)
-            .min_height(300.)
-            // .fixed_size(vec2(400.0, 400.0))
-            // .vscroll(true) // todo: temp fix for debug data on bottom
-            .show(ctx, |ui| self.ui(ui, toasts));
-    }
-}
+            ui.horizontal(|ui| {
+                if ui.button("Add Curve").clicked() {
+                    self.add_curve();
+                }
+                if ui.button("Duplicate").clicked() {
+                    self.duplicate_curve();
+                }
+            });
+        });
 
-impl CurveEditor {
-    fn ensure_drawing_points_capacity(&mut self) {
-        if self.points.len() != self.points_for_drawing.len() {
-            self.points_for_drawing = self.points.iter().map(|p| p.into()).collect();
-        }
-    }
+        ui.collapsing("Key", |ui| {
+            let Some(index) = self.selected_index() else {
+                ui.label("Click a key to inspect it.");
+                return;
+            };
+            let count = self.points().len();
+            let editable = self.editable();
+            let key = &mut self.points_mut()[index];
+            ui.label(format!(
+                "Key {} of {count} at ({:.4}, {:.4})",
+                index + 1,
+                key.pos.x,
+                key.pos.y
+            ));
+            let mut apply_to_all = None;
+            let mut changed = false;
+            ui.add_enabled_ui(editable, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Interpolation");
+                    if ui
+                        .radio(key.tcb.is_none(), "Bézier")
+                        .on_hover_text("Tangents are handles edited by hand")
+                        .clicked()
+                    {
+                        changed |= key.tcb.take().is_some();
+                    }
+                    if ui
+                        .radio(key.tcb.is_some(), "TCB")
+                        .on_hover_text("Tangents follow the neighbouring keys, shaped by tension, continuity and bias")
+                        .clicked()
+                        && key.tcb.is_none()
+                    {
+                        key.tcb = Some(splines::Tcb::default());
+                        changed = true;
+                    }
+                });
+                if let Some(tcb) = &mut key.tcb {
+                    changed |= ui
+                        .add(Slider::new(&mut tcb.tension, -1.0..=1.0).text("Tension"))
+                        .on_hover_text("1 flattens the tangents, -1 rounds the curve out")
+                        .changed();
+                    changed |= ui
+                        .add(Slider::new(&mut tcb.continuity, -1.0..=1.0).text("Continuity"))
+                        .on_hover_text("0 is smooth, -1 makes a corner and 1 a kink that bulges outwards")
+                        .changed();
+                    changed |= ui
+                        .add(Slider::new(&mut tcb.bias, -1.0..=1.0).text("Bias"))
+                        .on_hover_text("1 overshoots the key, -1 heads for the next key early")
+                        .changed();
+                    if ui
+                        .button("Apply to All Keys")
+                        .on_hover_text("Make every key of the channel a TCB key with these settings")
+                        .clicked()
+                    {
+                        apply_to_all = Some(*tcb);
+                    }
+                }
+            });
+            if let Some(tcb) = apply_to_all {
+                self.points_mut().iter_mut().for_each(|key| key.tcb = Some(tcb));
+                changed = true;
+            }
+            if changed {
+                self.constrain_all_keys();
+            }
+        });
+
+        ui.collapsing("Presets", |ui| {
+            let builtin_id = Id::new(BUILTIN_PRESETS_ID);
+            let builtin = ui.data_mut(|d| {
+                d.get_temp_mut_or_insert_with(builtin_id, || Arc::new(presets::builtin_presets()))
+                    .clone()
+            });
+            let user_id = Id::new(USER_PRESETS_ID);
+            let mut user: Vec<Preset> = ui.data_mut(|d| d.get_persisted(user_id)).unwrap_or_default();
+            let mut insert = None;
+            let mut remove = None;
+
+            ui.label("Click a preset to replace the keys, stretched over the domain and range.");
+            ui.add_enabled_ui(self.editable(), |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for preset in builtin.iter() {
+                        if preset_thumbnail(ui, preset).clicked() {
+                            insert = Some(preset.keys.clone());
+                        }
+                    }
+                });
+                if !user.is_empty() {
+                    ui.label("Saved");
+                    ui.horizontal_wrapped(|ui| {
+                        for (i, preset) in user.iter().enumerate() {
+                            let response = preset_thumbnail(ui, preset).context_menu(|ui| {
+                                if ui.button("Delete").clicked() {
+                                    remove = Some(i);
+                                    ui.close_menu();
+                                }
+                            });
+                            if response.clicked() {
+                                insert = Some(preset.keys.clone());
+                            }
+                        }
+                    });
+                }
+            });
 
-    fn intersected_key(&self, ptr_coord: Pos2) -> Option<(usize, AnimationKeyPointField)> {
-        for (i, pt) in self.points.iter().enumerate() {
-            if let Some(key_point_field) = pt.intersects(ptr_coord) {
-                // filter out first/last tangent on first/last element
-                if (i == 0 && key_point_field == AnimationKeyPointField::TanIn)
-                    || (i == self.points.len() - 1 && key_point_field == AnimationKeyPointField::TanOut)
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut self.preset_name)
+                        .hint_text("Preset name")
+                        .desired_width(120.0),
+                );
+                let name = self.preset_name.trim();
+                if ui
+                    .add_enabled(!name.is_empty(), Button::new("Save Current"))
+                    .on_hover_text("Save the keys as a preset, replacing any with the same name. Right click a saved preset to delete it.")
+                    .clicked()
                 {
-                    continue;
+                    let preset = Preset::new(name, self.preset_keys());
+                    match user.iter_mut().find(|p| p.name == preset.name) {
+                        Some(existing) => *existing = preset,
+                        None => user.push(preset),
+                    }
+                    toasts.info(format!("saved preset {name}"));
+                    self.preset_name.clear();
+                    ui.data_mut(|d| d.insert_persisted(user_id, user.clone()));
                 }
+            });
 
-                return Some((i, key_point_field));
+            if let Some(index) = remove {
+                let preset = user.remove(index);
+                toasts.info(format!("deleted preset {}", preset.name));
+                ui.data_mut(|d| d.insert_persisted(user_id, user));
             }
-        }
-        None
-    }
+            if let Some(keys) = insert {
+                self.insert_preset(&keys);
+            }
+        });
 
-    fn add_key(&mut self, pos: Vec2) {
-        let y_min = if self.constrain_to_01 { 0. } else { -1. };
-        let new_pos = pos.clamp(vec2(0., y_min), vec2(1., 1.));
+        ui.collapsing("Generators", |ui| {
+            let attachable = self.editable();
+            let tolerance = self.fit_tolerance;
+            let curve = &mut self.curves[self.active];
+            let Some(generator) = &mut curve.generator else {
+                ui.label("Replace the keys of every channel with a physical motion across the domain and range.");
+                let mut attach = None;
+                ui.add_enabled_ui(attachable, |ui| {
+                    ui.horizontal(|ui| {
+                        for motion in Motion::ALL {
+                            if ui.button(motion.name()).clicked() {
+                                attach = Some(motion);
+                            }
+                        }
+                    });
+                });
+                if let Some(motion) = attach {
+                    self.attach_generator(motion);
+                }
+                return;
+            };
 
-        self.points.push(AnimationKey::new(new_pos));
-        self.points_for_drawing.push(self.points.last().unwrap().into());
-        self.points.sort_by(|a, b| a.pos.x.partial_cmp(&b.pos.x).unwrap());
-    }
+            ui.label(format!(
+                "The keys of {} follow a {}. Edit its parameters, or convert it to keys to edit them by hand.",
+                curve.name,
+                generator.motion.name().to_lowercase()
+            ));
+            let mut changed = false;
+            let mut convert = false;
+            ui.add_enabled_ui(curve.visible && !curve.locked, |ui| {
+                changed = generator_ui(ui, generator);
+                ui.horizontal(|ui| {
+                    if let Motion::Spring { .. } = generator.motion {
+                        if ui
+                            .button("Real Time")
+                            .on_hover_text("Make the curve last as long as the spring takes to settle, in seconds")
+                            .clicked()
+                        {
+                            generator.end.x = generator.start.x + generator.motion.duration();
+                            changed = true;
+                        }
+                    }
+                    convert = ui
+                        .button("Convert to Keys")
+                        .on_hover_text("Keep the keys and drop the motion so they can be edited by hand")
+                        .clicked();
+                });
+            });
+            let generator = generator.clone();
+            if changed {
+                curve.generate(tolerance);
+            }
 
-    fn update_dragged_object(&mut self, drag_delta: Vec2) {
-        if let Some(ref mut dragged) = self.dragged_object {
-            self.points[dragged.0].translate(&dragged.1, drag_delta);
+            let mut text = format!(
+                "{} keys, max error {:.5}",
+                curve.keys().len(),
+                generator.max_error(curve.keys())
+            );
+            if let Motion::Spring { .. } = generator.motion {
+                text += &format!(". Settles after {:.3} s", generator.motion.duration());
+            }
+            ui.label(text);
 
-            if dragged.1 == AnimationKeyPointField::Pos {
-                let key = self.points[dragged.0].clone();
-                self.points.sort_by(|a, b| a.pos.x.partial_cmp(&b.pos.x).unwrap());
-                dragged.0 = self.points.iter().position(|e| *e == key).unwrap();
+            if convert {
+                curve.generator = None;
+                toasts.info(format!("converted {} to keys", curve.name));
             }
-        }
-    }
+            if changed || convert {
+                self.dragged_object = None;
+                self.hovered_object = None;
+                self.preview = None;
+            }
+        });
 
-    fn draw_curve(&self) -> Line {
-        let pts = splines::flatten(&self.points, Some(self.curve_resolution));
-        Line::new(pts).color(CURVE_COLOR).style(LineStyle::Solid)
-    }
+        ui.collapsing("Modifiers", |ui| {
+            let new_modifiers = self.new_modifiers();
+            let range = self.limits().range;
+            let editable = self.editable();
+            let curve = &mut self.curves[self.active];
+            ui.label(format!(
+                "Layered on every channel of {} from the top without changing its keys.",
+                curve.name
+            ));
+
+            let mut bake = false;
+            ui.add_enabled_ui(editable, |ui| {
+                let count = curve.modifiers.len();
+                let mut move_down = None;
+                let mut remove = None;
+                for (i, modifier) in curve.modifiers.iter_mut().enumerate() {
+                    ui.push_id(i, |ui| {
+                        ui.horizontal(|ui| {
+                            let name = modifier.name();
+                            ui.checkbox(&mut modifier.enabled, name);
+                            if ui
+                                .add_enabled(i > 0, Button::new("⏶"))
+                                .on_hover_text("Move up")
+                                .clicked()
+                            {
+                                move_down = Some(i - 1);
+                            }
+                            if ui
+                                .add_enabled(i + 1 < count, Button::new("⏷"))
+                                .on_hover_text("Move down")
+                                .clicked()
+                            {
+                                move_down = Some(i);
+                            }
+                            if ui.button("🗑").on_hover_text("Remove the modifier").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                        ui.indent("modifier", |ui| modifier_ui(ui, &mut modifier.kind, range));
+                    });
+                }
+                if let Some(index) = move_down {
+                    curve.modifiers.swap(index, index + 1);
+                }
+                if let Some(index) = remove {
+                    curve.modifiers.remove(index);
+                }
 
-    fn draw_curve_no_tolerance(&self) -> Line {
-        let orig_pts = splines::flatten(&self.points, None);
-        Line::new(orig_pts)
-            .color(Color32::from_rgba_premultiplied(0, 200, 0, 8))
-            .style(LineStyle::Solid)
-    }
+                ui.horizontal(|ui| {
+                    ui.menu_button("Add Modifier", |ui| {
+                        for modifier in new_modifiers {
+                            if ui.button(modifier.name()).clicked() {
+                                curve.modifiers.push(modifier);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                    bake = ui
+                        .add_enabled(curve.has_modifiers(), Button::new("Bake to Keys"))
+                        .on_hover_text(
+                            "Replace the keys with the fewest keys within the fit tolerance of the modified curve across \
+                            the domain, and remove the modifiers",
+                        )
+                        .clicked();
+                });
+            });
+            if bake {
+                self.bake_modifiers(toasts);
+            }
+        });
 
-    fn draw_tangent_lines(&self, plot_ui: &mut PlotUi) {
-        for (i, pt) in self.points.iter().enumerate() {
-            let mut pts = Vec::with_capacity(3);
+        ui.collapsing("Domain & Range", |ui| {
+            let mut changed = false;
+            let limits = self.limits_mut();
+            Grid::new("curve_limits").num_columns(4).show(ui, |ui| {
+                changed |= axis_limits_ui(ui, "Domain (x)", &mut limits.domain);
+                ui.end_row();
+                changed |= axis_limits_ui(ui, "Range (y)", &mut limits.range);
+                ui.end_row();
+            });
 
-            let pos = [pt.pos.x as f64, pt.pos.y as f64];
-            if i > 0 {
-                pts.push([pos[0] + pt.tangent_in.x as f64, pos[1] + pt.tangent_in.y as f64]);
-            }
-            pts.push(pos);
+            ui.horizontal(|ui| {
+                if ui.button("Range 0 - 1").clicked() {
+                    self.limits_mut().range = AxisLimits::new(0.0, 1.0);
+                    changed = true;
+                }
+                if ui.button("Range -1 - 1").clicked() {
+                    self.limits_mut().range = AxisLimits::new(-1.0, 1.0);
+                    changed = true;
+                }
+                if ui.button("Normalize to 0 - 1").clicked() {
+                    self.normalize_keys();
+                    toasts.info("keys normalized");
+                }
+            });
 
-            if i < self.points.len() - 1 {
-                pts.push([pos[0] + pt.tangent_out.x as f64, pos[1] + pt.tangent_out.y as f64]);
+            if changed {
+                self.clamp_keys();
             }
+        });
 
-            plot_ui.line(Line::new(pts).color(CONTROL_POINT_LINE_COLOR));
-        }
-    }
-
-    fn draw_keys(&self, painter: Painter) {
-        for (i, pt) in self.points_for_drawing.iter().enumerate() {
-            let ctrl_pt_color = if self.points[i].tangent_locked {
-                CONTROL_POINT_COLOR
-            } else {
-                CONTROL_POINT_UNLOCKED_COLOR
-            };
+        ui.collapsing("Sample Data", |ui| {
+            ui.horizontal(|ui| {
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Import CSV...").clicked() {
+                    self.import_samples(toasts);
+                }
+                if ui.button("Load Pasted").clicked() {
+                    let text = std::mem::take(&mut self.samples_text);
+                    self.load_samples(&text, toasts);
+                    self.samples_text = text;
+                }
+                if ui.add_enabled(!self.samples.is_empty(), Button::new("Clear")).clicked() {
+                    self.samples.clear();
+                }
+            });
+            ui.add(
+                TextEdit::multiline(&mut self.samples_text)
+                    .code_editor()
+                    .desired_rows(3)
+                    .hint_text("x,y pairs, one per line"),
+            );
 
-            // dont draw both tangents for first or last keys
-            if i > 0 {
-                painter.circle_filled(pt.tangent_in.to_pos2(), CONTROL_POINT_RADIUS, ctrl_pt_color);
+            if self.samples.is_empty() {
+                return;
             }
 
-            painter.circle_filled(pt.pos.to_pos2(), POINT_RADIUS, POINT_COLOR);
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.sample_fit_to_tolerance, true, "Within Fit Tolerance");
+                ui.radio_value(&mut self.sample_fit_to_tolerance, false, "Key Count");
+                ui.add_enabled(
+                    !self.sample_fit_to_tolerance,
+                    DragValue::new(&mut self.sample_fit_keys).clamp_range(2..=fitting::MAX_SAMPLE_FIT_KEYS),
+                );
+                if ui
+                    .button("Fit Keys")
+                    .on_hover_text("Preview keys fitted to the samples by least squares")
+                    .clicked()
+                {
+                    self.preview_sample_fit(toasts);
+                }
+            });
 
-            if i < self.points_for_drawing.len() - 1 {
-                painter.circle_filled(pt.tangent_out.to_pos2(), CONTROL_POINT_RADIUS, ctrl_pt_color);
+            let current = fitting::residual(self.points(), &self.samples);
+            let mut text = format!(
+                "{} samples. Curve error: max {:.5}, rms {:.5}",
+                self.samples.len(),
+                current.max,
+                current.rms
+            );
+            if let Some(preview) = &self.preview {
+                let fitted = fitting::residual(&preview.points, &self.samples);
+                text += &format!(". Preview error: max {:.5}, rms {:.5}", fitted.max, fitted.rms);
             }
+            ui.label(text);
+        });
 
-            if let Some(hovered) = self.hovered_object {
-                if hovered.0 == i {
-                    let stroke = Stroke::new(2.0, HOVERED_KEY_STROKE_COLOR);
-                    match hovered.1 {
-                        AnimationKeyPointField::Pos => painter.circle_stroke(pt.pos.to_pos2(), POINT_RADIUS, stroke),
-                        AnimationKeyPointField::TanIn => {
-                            painter.circle_stroke(pt.tangent_in.to_pos2(), CONTROL_POINT_RADIUS, stroke)
-                        }
-                        AnimationKeyPointField::TanOut => {
-                            painter.circle_stroke(pt.tangent_out.to_pos2(), CONTROL_POINT_RADIUS, stroke)
+        ui.collapsing("Expression", |ui| {
+            let parsed = Expression::parse(&self.expression_text);
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut self.expression_text)
+                        .code_editor()
+                        .desired_width(200.0)
+                        .hint_text("sin(x * pi)^2"),
+                )
+                .on_hover_text("A formula of x, like 1 - exp(-4x), with the usual functions and pi, tau and e");
+                ui.toggle_value(&mut self.show_expression, "Show Overlay");
+                if ui
+                    .add_enabled(parsed.is_ok() && self.editable(), Button::new("Fit Keys"))
+                    .on_hover_text(
+                        "Preview the fewest keys within the fit tolerance of the expression across the domain",
+                    )
+                    .clicked()
+                {
+                    if let Ok(expression) = &parsed {
+                        if let Err(err) = self.preview_expression_fit(expression) {
+                            toasts.error(err);
                         }
                     }
                 }
+            });
+
+            match &parsed {
+                Err(err) if !self.expression_text.trim().is_empty() => {
+                    ui.colored_label(ui.visuals().error_fg_color, format!("⚠ {err}"));
+                }
+                Ok(expression) if !self.points().is_empty() => {
+                    let samples = self.domain_samples();
+                    let error = |keys: &[AnimationKey]| {
+                        samples
+                            .iter()
+                            .map(|&x| (splines::evaluate(keys, x) - expression.evaluate(x)).abs())
+                            .fold(0.0, f64::max)
+                    };
+                    let mut text = format!("Curve error: max {:.5}", error(self.points()));
+                    if let Some(preview) = &self.preview {
+                        text += &format!(". Preview error: max {:.5}", error(&preview.points));
+                    }
+                    ui.label(text);
+                }
+                _ => {}
             }
-        }
-    }
-}
+        });
 
-impl CurveEditor {
-    fn ui(&mut self, ui: &mut Ui, toasts: &mut Toasts) -> Response {
-        ui.horizontal(|ui| {
-            ui.collapsing("Instructions", |ui| {
-                ui.label("Command/Ctrl click tangent to toggle tangent lock (or right-click for menu).");
-                ui.label("Alt click key to delete (or right click for menu).");
-                ui.label("Alt click empty space to add a key (or right click for menu).");
+        ui.collapsing("Export Samples", |ui| {
+            ui.horizontal(|ui| {
+                let rate = &mut self.export.rate;
+                if ui.radio(matches!(rate, csv::SampleRate::Steps(_)), "Steps").clicked() {
+                    *rate = csv::SampleRate::Steps(100);
+                }
+                if ui.radio(matches!(rate, csv::SampleRate::Step(_)), "X Step").clicked() {
+                    *rate = csv::SampleRate::Step(0.01);
+                }
+                if ui
+                    .radio(matches!(rate, csv::SampleRate::EvenLength(_)), "Even Length")
+                    .on_hover_text("Points spaced evenly along the length of each curve instead of along x")
+                    .clicked()
+                {
+                    *rate = csv::SampleRate::EvenLength(101);
+                }
+                match rate {
+                    csv::SampleRate::Steps(steps) => ui.add(DragValue::new(steps).clamp_range(1..=csv::MAX_ROWS)),
+                    csv::SampleRate::Step(step) => {
+                        ui.add(DragValue::new(step).speed(0.001).clamp_range(0.0001..=f64::MAX))
+                    }
+                    csv::SampleRate::EvenLength(count) => ui.add(DragValue::new(count).clamp_range(2..=csv::MAX_ROWS)),
+                };
+
+                ui.checkbox(&mut self.export.header, "Header");
+                ComboBox::from_id_source("csv_delimiter")
+                    .selected_text(self.export.delimiter.name())
+                    .show_ui(ui, |ui| {
+                        for delimiter in csv::Delimiter::ALL {
+                            ui.selectable_value(&mut self.export.delimiter, delimiter, delimiter.name());
+                        }
+                    });
+            });
+
+            if matches!(self.export.rate, csv::SampleRate::EvenLength(_)) && !self.points().is_empty() {
+                let length =
+                    splines::arc_length_table(self.points(), &self.curves[self.active].modifiers, None).length();
+                ui.label(format!("Length of the active curve: {length:.4}"));
+            }
+
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Copy CSV")
+                    .on_hover_text("Copy the sample rows to the clipboard")
+                    .clicked()
+                {
+                    ui.output_mut(|o| o.copied_text = self.export_csv());
+                    toasts.info("CSV copied to clipboard");
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Save CSV...").clicked() {
+                    self.save_csv(toasts);
+                }
             });
         });
-        ui.separator();
 
-        ui.horizontal(|ui| {
-            ui.style_mut().wrap = Some(false);
-            if ui
-                .toggle_value(&mut self.constrain_to_01, "Constrain to 0 - 1 Range")
-                .changed()
-            {
-                if self.constrain_to_01 {
-                    for pt in &mut self.points {
-                        (*pt).pos.y = pt.pos.y.clamp(0., 1.);
-                    }
+        ui.collapsing("Document", |ui| {
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Copy Curves")
+                    .on_hover_text("Copy every curve as RON, which the curve crate can load at runtime")
+                    .clicked()
+                {
+                    ui.output_mut(|o| o.copied_text = self.curves_ron());
+                    toasts.info("curves copied to clipboard");
                 }
-            }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Save Curves...").clicked() {
+                    self.save_curves(toasts);
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Open Curves...").clicked() {
+                    self.open_curves(toasts);
+                }
+            });
+        });
 
-            ui.add(
-                egui::Slider::new(&mut self.curve_resolution, 0.0001..=0.1)
-                    .logarithmic(true)
-                    .text("Curve Tolerance"),
-            );
+        ui.collapsing("Polynomial", |ui| {
+            let polynomial = self.fit_polynomial(self.points(), &self.limits().domain);
+            ui.horizontal(|ui| {
+                ui.add(
+                    DragValue::new(&mut self.polynomial_degree)
+                        .clamp_range(1..=fitting::MAX_POLYNOMIAL_DEGREE)
+                        .prefix("Degree: "),
+                );
+                ui.toggle_value(&mut self.show_polynomial, "Show Overlay");
+                ui.label(format!(
+                    "{} max error {:.5}",
+                    self.curves[self.active].name,
+                    fitting::polynomial_error(self.points(), &polynomial)
+                ));
+            });
 
-            egui::reset_button(ui, self);
+            ui.horizontal(|ui| {
+                ComboBox::from_id_source("polynomial_language")
+                    .selected_text(self.code_language.name())
+                    .show_ui(ui, |ui| {
+                        for language in codegen::Language::ALL {
+                            ui.selectable_value(&mut self.code_language, language, language.name());
+                        }
+                    });
+                if ui
+                    .button("Copy Code")
+                    .on_hover_text("Copy a function for every curve")
+                    .clicked()
+                {
+                    ui.output_mut(|o| o.copied_text = self.polynomial_code());
+                    toasts.info("code copied to clipboard");
+                }
+            });
+            let code = self.polynomial_code();
+            crate::syntax_highlighting::code_view_ui(ui, &code);
+        });
+
+        let playhead = if self.playing { self.sample_playhead() } else { None };
+        ui.collapsing("Playback", |ui| {
+            ui.horizontal(|ui| {
+                let label = if self.playing { "⏹ Stop" } else { "▶ Play" };
+                if ui.button(label).clicked() {
+                    self.playing = !self.playing;
+                    self.playhead = self.points().first().map_or(0.0, |p| p.pos.x);
+                }
+                ui.add(
+                    DragValue::new(&mut self.playback_speed)
+                        .speed(0.01)
+                        .clamp_range(-100.0..=100.0)
+                        .prefix("Speed: "),
+                );
+                ComboBox::from_id_source("playback_wrap")
+                    .selected_text(self.playback_cursor.wrap.name())
+                    .show_ui(ui, |ui| {
+                        for wrap in WrapMode::ALL {
+                            ui.selectable_value(&mut self.playback_cursor.wrap, wrap, wrap.name());
+                        }
+                    });
+            });
+
+            if let Some((_, y, offset)) = playhead {
+                ui.label(format!("x {:.3} → {:.3}", self.playhead, y + offset));
+            }
         });
+        if self.playing {
+            self.playhead += ui.input(|i| i.stable_dt) as f64 * self.playback_speed;
+            ui.ctx().request_repaint();
+        }
 
         self.ensure_drawing_points_capacity();
+        if self
+            .preview
+            .as_ref()
+            .map_or(false, |preview| preview.source != *self.points())
+        {
+            self.preview = None;
+        }
 
+        let plot_bounds = self.plot_bounds();
         let mut plot = Plot::new("lines_demo")
-            .allow_drag(false && self.dragged_object.is_none())
-            .allow_scroll(false && self.dragged_object.is_none())
-            .allow_zoom(false && self.dragged_object.is_none())
-            .allow_boxed_zoom(false && self.dragged_object.is_none())
+            .allow_drag(false)
+            .allow_scroll(false)
+            .allow_zoom(false)
+            .allow_boxed_zoom(false)
             .allow_double_click_reset(false)
             .show_x(false)
             .show_y(false)
             .min_size(vec2(128., 128.))
+            .legend(Legend::default())
             // .data_aspect(1.0)
             // .view_aspect(1.0)
             .height(ui.available_height());
@@ -340,17 +1848,25 @@ impl CurveEditor {
 
         let InnerResponse {
             mut response,
-            inner: (left_click_pos, drag_delta, ptr_coord, _ptr_coord_screen, _bounds),
+            inner: (left_click_pos, drag_delta, ptr_coord, ptr_screen, right_click_screen),
         } = plot.show(ui, |plot_ui| {
+            self.draw_samples(plot_ui);
+
             // draw the curve
-            plot_ui.line(self.draw_curve());
-            plot_ui.line(self.draw_curve_no_tolerance());
-            self.draw_tangent_lines(plot_ui);
+            self.draw_curves(plot_ui);
+            if self.curves[self.active].visible {
+                plot_ui.line(self.draw_curve_no_tolerance());
+                self.draw_overshooting_segments(plot_ui);
+            }
+            self.draw_expression(plot_ui);
+            self.draw_preview(plot_ui);
+            self.draw_polynomial(plot_ui);
+            self.draw_playhead(plot_ui, playhead);
+            if self.curves[self.active].visible {
+                self.draw_tangent_lines(plot_ui);
+            }
 
-            let y_min = if self.constrain_to_01 { 0. } else { -1. };
-            let min_bounds = [0. - BOUNDS_OVERSHOOT, y_min - BOUNDS_OVERSHOOT];
-            let max_bounds = [1. + BOUNDS_OVERSHOOT, 1. + BOUNDS_OVERSHOOT];
-            plot_ui.set_plot_bounds(PlotBounds::from_min_max(min_bounds, max_bounds));
+            plot_ui.set_plot_bounds(plot_bounds);
 
             let left_click_pos = plot_ui.ctx().input(|i| {
                 if i.pointer.primary_clicked() {
@@ -360,7 +1876,7 @@ impl CurveEditor {
             });
 
             // convert to screen space for drawing
-            for (i, pt) in self.points.iter().enumerate() {
+            for (i, pt) in self.curves[self.active].keys().iter().enumerate() {
                 self.points_for_drawing[i].pos = plot_ui.screen_from_plot(PlotPoint::new(pt.pos.x, pt.pos.y)).to_vec2();
                 self.points_for_drawing[i].tangent_in = plot_ui
                     .screen_from_plot(PlotPoint::new(pt.pos.x + pt.tangent_in.x, pt.pos.y + pt.tangent_in.y))
@@ -374,41 +1890,35 @@ impl CurveEditor {
                 left_click_pos,
                 plot_ui.pointer_coordinate_drag_delta(),
                 plot_ui.pointer_coordinate(),
-                if let Some(pt) = plot_ui.pointer_coordinate() {
-                    Some(plot_ui.screen_from_plot(pt))
-                } else {
-                    None
-                },
-                plot_ui.plot_bounds(),
+                plot_ui.pointer_coordinate().map(|pt| plot_ui.screen_from_plot(pt)),
+                self.right_click_pos.map(|pt| plot_ui.screen_from_plot(pt)),
             )
         });
 
         // check for click/drag
-        if left_click_pos.is_some() && ptr_coord.is_some() {
-            let ptr_coord = ptr_coord.unwrap().to_pos2();
-            self.dragged_object = self.intersected_key(ptr_coord);
+        if let (Some(click_pos), Some(_)) = (left_click_pos, ptr_coord) {
+            self.dragged_object = self.intersected_key(click_pos);
+            if let Some((index, _)) = self.dragged_object {
+                self.selected_key = Some(self.points()[index].id);
+            }
         }
 
-        if response.drag_released() {
-            if let Some(dragged) = &self.dragged_object {
-                if dragged.1 == AnimationKeyPointField::Pos {
-                    let y_min = if self.constrain_to_01 { 0. } else { -1. };
-                    self.points[dragged.0].pos = self.points[dragged.0].pos.clamp(vec2(0., y_min), vec2(1., 1.));
-                }
-                self.dragged_object = None;
+        if let (true, Some(dragged)) = (response.drag_released(), &self.dragged_object) {
+            if dragged.1 == AnimationKeyPointField::Pos {
+                let index = dragged.0;
+                let pos = self.limits().clamp(self.points()[index].pos);
+                self.points_mut()[index].pos = pos;
+                self.constrain_key(index);
             }
+            self.dragged_object = None;
         }
 
         // handle dragging keys
         self.update_dragged_object(drag_delta);
 
         // hover cursor if ptr is in plot rect
-        if let Some(mut ptr_coord) = ptr_coord {
-            if let Some(right_click_pos) = self.right_click_pos {
-                ptr_coord = PlotPoint::new(right_click_pos.x as f64, right_click_pos.y as f64);
-            }
-
-            if let (None, Some(hovered)) = (&self.dragged_object, self.intersected_key(ptr_coord.to_pos2())) {
+        if let Some(ptr_screen) = right_click_screen.or(ptr_screen) {
+            if let (None, Some(hovered)) = (&self.dragged_object, self.intersected_key(ptr_screen)) {
                 self.hovered_object = Some(hovered);
                 if hovered.1 != AnimationKeyPointField::Pos && ui.input(|i| i.modifiers.command) {
                     response = response.on_hover_cursor(CursorIcon::Crosshair);
@@ -421,7 +1931,9 @@ impl CurveEditor {
         }
 
         // draw the keys
-        self.draw_keys(ui.painter_at(response.rect));
+        if self.curves[self.active].visible {
+            self.draw_keys(ui.painter_at(response.rect));
+        }
 
         if self.hovered_object.is_some() {
             let mut showing_contex_menu = false;
@@ -429,14 +1941,14 @@ impl CurveEditor {
                 showing_contex_menu = true;
 
                 if let (None, Some(ptr_pos)) = (self.right_click_pos, ptr_coord) {
-                    self.right_click_pos = Some(Pos2::new(ptr_pos.x as f32, ptr_pos.y as f32));
+                    self.right_click_pos = Some(ptr_pos);
                 }
 
-                let hovered = self.hovered_object.as_ref().unwrap();
+                let hovered = self.hovered_object.unwrap();
                 match hovered.1 {
                     AnimationKeyPointField::Pos => {
                         if ui
-                            .add_enabled_ui(self.points.len() > 2, |ui| {
+                            .add_enabled_ui(self.points().len() > 2, |ui| {
                                 if ui.button("Delete Key").clicked() {
                                     ui.close_menu();
                                     return true;
@@ -445,18 +1957,18 @@ impl CurveEditor {
                             })
                             .inner
                         {
-                            self.points.remove(hovered.0);
+                            self.points_mut().remove(hovered.0);
                             self.hovered_object = None;
                         }
                     }
                     _ => {
-                        let text = if self.points[hovered.0].tangent_locked {
+                        let text = if self.points()[hovered.0].tangent_locked {
                             "Unlock Tangent"
                         } else {
                             "Lock Tangent"
                         };
                         if ui.button(text).clicked() {
-                            self.points[hovered.0].toggle_tangent(hovered.1);
+                            toggle_tangent(&mut self.points_mut()[hovered.0], hovered.1);
                             toasts.info("tangent lock toggled");
                             ui.close_menu();
                         }
@@ -470,35 +1982,32 @@ impl CurveEditor {
             });
 
             // if the context menu is closed unset the hovered object and handle clicks
-            if !showing_contex_menu {
-                if response.clicked() {
-                    let hovered = self.hovered_object.unwrap();
-                    if hovered.1 != AnimationKeyPointField::Pos && ui.input(|i| i.modifiers.command) {
-                        self.points[hovered.0].toggle_tangent(hovered.1);
-                        toasts.info("tangent lock toggled");
-                    } else if hovered.1 == AnimationKeyPointField::Pos && ui.input(|i| i.modifiers.alt) {
-                        if self.points.len() > 2 {
-                            self.points.remove(hovered.0);
-                            toasts.info("key removed");
-                        } else {
-                            toasts.error("cannot remove key");
-                        }
+            if !showing_contex_menu && response.clicked() {
+                let hovered = self.hovered_object.unwrap();
+                if hovered.1 != AnimationKeyPointField::Pos && ui.input(|i| i.modifiers.command) {
+                    toggle_tangent(&mut self.points_mut()[hovered.0], hovered.1);
+                    toasts.info("tangent lock toggled");
+                } else if hovered.1 == AnimationKeyPointField::Pos && ui.input(|i| i.modifiers.alt) {
+                    if self.points().len() > 2 {
+                        self.points_mut().remove(hovered.0);
+                        toasts.info("key removed");
+                    } else {
+                        toasts.error("cannot remove key");
                     }
                 }
-
-                // self.hovered_object = None;
             }
+            // self.hovered_object = None;
         } else {
             let mut showing_contex_menu = false;
             response = response.context_menu(|ui| {
                 showing_contex_menu = true;
 
                 if let (None, Some(ptr_pos)) = (self.right_click_pos, ptr_coord) {
-                    self.right_click_pos = Some(Pos2::new(ptr_pos.x as f32, ptr_pos.y as f32));
+                    self.right_click_pos = Some(ptr_pos);
                 }
 
-                if ui.button("Add Key Here").clicked() {
-                    self.add_key(self.right_click_pos.unwrap().to_vec2());
+                if ui.add_enabled(self.editable(), Button::new("Add Key Here")).clicked() {
+                    self.add_key(from_plot(self.right_click_pos.unwrap()));
                     toasts.info("key added");
                     ui.close_menu();
                 }
@@ -512,14 +2021,327 @@ impl CurveEditor {
                 self.right_click_pos = None;
 
                 // alt click to add point
-                if response.clicked() && ui.input(|i| i.modifiers.alt) {
-                    self.add_key(ptr_coord.unwrap().to_vec2());
+                if response.clicked() && ui.input(|i| i.modifiers.alt) && self.editable() {
+                    self.add_key(from_plot(ptr_coord.unwrap()));
                     toasts.info("key added");
                 }
             }
         }
 
         self.hovered_object = None;
+        // syncing moves the keys of the other channels, which the tangents of their TCB keys have to follow
+        if self.curves[self.active].sync_channels() && self.editable() {
+            self.for_each_channel(|editor| {
+                if editor.points().iter().any(|key| key.tcb.is_some()) {
+                    editor.constrain_all_keys();
+                }
+            });
+        }
         response
     }
 }
+
+/// `color` for the first channel of a curve, lightened for each later channel so they can be told apart
+fn channel_color(color: Color32, channel: usize) -> Color32 {
+    let t = channel as f32 / document::MAX_CHANNELS as f32;
+    let lighten = |c: u8| (c as f32 + (255.0 - c as f32) * t).round() as u8;
+    Color32::from_rgba_unmultiplied(lighten(color.r()), lighten(color.g()), lighten(color.b()), color.a())
+}
+
+/// Hit tests `ptr_screen` against keys and tangents drawn at `points`. A pointer inside a drawn circle picks the
+/// topmost such circle (the one drawn last). Otherwise the nearest circle within `PICK_TOLERANCE` of its edge wins.
+/// The outer tangents of the first and last key are only there when `closed`.
+pub fn pick_handle(
+    points: &[AnimationKeyPoint],
+    ptr_screen: Pos2,
+    closed: bool,
+) -> Option<(usize, AnimationKeyPointField)> {
+    let last = points.len().checked_sub(1)?;
+    let mut best: Option<(usize, AnimationKeyPointField)> = None;
+    let mut best_inside = false;
+    let mut best_dist = f32::MAX;
+
+    for (i, pt) in points.iter().enumerate() {
+        for (field, center, radius) in pt.handles() {
+            // the first/last tangent on the first/last key are not drawn
+            if !closed
+                && ((i == 0 && field == AnimationKeyPointField::TanIn)
+                    || (i == last && field == AnimationKeyPointField::TanOut))
+            {
+                continue;
+            }
+
+            let dist = center.distance(ptr_screen) - radius;
+            if dist > PICK_TOLERANCE {
+                continue;
+            }
+
+            let inside = dist <= 0.0;
+            if (inside && !best_inside) || (inside == best_inside && (inside || dist < best_dist)) {
+                best = Some((i, field));
+                best_inside = inside;
+                best_dist = dist;
+            }
+        }
+    }
+    best
+}
+
+/// draws `keys` at their screen positions `points`, outlining the `hovered` handle. The outer tangents of the first
+/// and last key are only drawn when `closed`.
+pub fn draw_handles(
+    painter: &Painter,
+    points: &[AnimationKeyPoint],
+    keys: &[AnimationKey],
+    hovered: Option<(usize, AnimationKeyPointField)>,
+    closed: bool,
+) {
+    for (i, pt) in points.iter().enumerate() {
+        let ctrl_pt_color = if keys[i].tangent_locked {
+            CONTROL_POINT_COLOR
+        } else {
+            CONTROL_POINT_UNLOCKED_COLOR
+        };
+
+        // dont draw both tangents for first or last keys
+        if i > 0 || closed {
+            painter.circle_filled(pt.tangent_in.to_pos2(), CONTROL_POINT_RADIUS, ctrl_pt_color);
+        }
+
+        painter.circle_filled(pt.pos.to_pos2(), POINT_RADIUS, POINT_COLOR);
+
+        if i < points.len() - 1 || closed {
+            painter.circle_filled(pt.tangent_out.to_pos2(), CONTROL_POINT_RADIUS, ctrl_pt_color);
+        }
+
+        if let Some(hovered) = hovered {
+            if hovered.0 == i {
+                let stroke = Stroke::new(2.0, HOVERED_KEY_STROKE_COLOR);
+                match hovered.1 {
+                    AnimationKeyPointField::Pos => painter.circle_stroke(pt.pos.to_pos2(), POINT_RADIUS, stroke),
+                    AnimationKeyPointField::TanIn => {
+                        painter.circle_stroke(pt.tangent_in.to_pos2(), CONTROL_POINT_RADIUS, stroke)
+                    }
+                    AnimationKeyPointField::TanOut => {
+                        painter.circle_stroke(pt.tangent_out.to_pos2(), CONTROL_POINT_RADIUS, stroke)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// the settings of a modifier, with limits toggled on starting at the ends of `range`
+fn modifier_ui(ui: &mut Ui, kind: &mut ModifierKind, range: AxisLimits) {
+    match kind {
+        ModifierKind::Cycles { before, after } => {
+            ui.horizontal(|ui| {
+                for (label, wrap) in [("Before", before), ("After", after)] {
+                    ComboBox::from_label(label)
+                        .selected_text(wrap.name())
+                        .show_ui(ui, |ui| {
+                            for mode in splines::WrapMode::ALL {
+                                ui.selectable_value(wrap, mode, mode.name());
+                            }
+                        });
+                }
+            });
+        }
+        ModifierKind::Noise {
+            seed,
+            amplitude,
+            frequency,
+        } => {
+            ui.horizontal(|ui| {
+                ui.add(DragValue::new(seed).prefix("Seed: "));
+                ui.add(DragValue::new(amplitude).speed(0.001).prefix("Amplitude: "));
+                ui.add(
+                    DragValue::new(frequency)
+                        .speed(0.1)
+                        .clamp_range(0.0..=f64::MAX)
+                        .prefix("Frequency: "),
+                );
+            });
+        }
+        ModifierKind::Envelope { reference, points } => {
+            ui.add(DragValue::new(reference).speed(0.01).prefix("Reference: "))
+                .on_hover_text("The value the factors scale away from");
+            let mut remove = None;
+            Grid::new("envelope").num_columns(3).show(ui, |ui| {
+                for (i, point) in points.iter_mut().enumerate() {
+                    ui.add(DragValue::new(&mut point.x).speed(0.01).prefix("x: "));
+                    ui.add(DragValue::new(&mut point.y).speed(0.01).prefix("Factor: "));
+                    if ui.button("🗑").clicked() {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(index) = remove {
+                points.remove(index);
+            }
+            if ui.button("Add Point").clicked() {
+                let x = points.last().map_or(0.0, |point| point.x + 0.1);
+                points.push(curve::vec2(x, 1.0));
+            }
+            points.sort_by(|a, b| a.x.total_cmp(&b.x));
+        }
+        ModifierKind::Stepped { step, offset } => {
+            ui.horizontal(|ui| {
+                ui.add(
+                    DragValue::new(step)
+                        .speed(0.001)
+                        .clamp_range(0.0001..=f64::MAX)
+                        .prefix("Step: "),
+                );
+                ui.add(DragValue::new(offset).speed(0.001).prefix("Offset: "));
+            });
+        }
+        ModifierKind::Limits { min, max } => {
+            ui.horizontal(|ui| {
+                for (label, bound, default) in [("Min", min, range.min), ("Max", max, range.max)] {
+                    let mut enabled = bound.is_some();
+                    if ui.checkbox(&mut enabled, label).changed() {
+                        *bound = enabled.then_some(default);
+                    }
+                    if let Some(value) = bound {
+                        ui.add(DragValue::new(value).speed(0.01));
+                    }
+                }
+            });
+        }
+    }
+}
+
+/// edits the parameters of a generator, returning whether any changed
+fn generator_ui(ui: &mut Ui, generator: &mut Generator) -> bool {
+    let mut changed = false;
+    Grid::new("generator").num_columns(2).show(ui, |ui| {
+        match &mut generator.motion {
+            Motion::Spring {
+                stiffness,
+                damping,
+                mass,
+                velocity,
+            } => {
+                ui.label("Stiffness");
+                changed |= ui
+                    .add(DragValue::new(stiffness).speed(1.0).clamp_range(0.01..=10_000.0))
+                    .changed();
+                ui.end_row();
+                ui.label("Damping");
+                changed |= ui
+                    .add(
+                        DragValue::new(damping)
+                            .speed(0.1)
+                            .clamp_range(generators::min_damping(*stiffness, *mass)..=1_000.0),
+                    )
+                    .changed();
+                ui.end_row();
+                ui.label("Mass");
+                changed |= ui
+                    .add(DragValue::new(mass).speed(0.01).clamp_range(0.01..=100.0))
+                    .changed();
+                ui.end_row();
+                ui.label("Initial Velocity");
+                changed |= ui
+                    .add(DragValue::new(velocity).speed(0.1).clamp_range(-100.0..=100.0))
+                    .on_hover_text("Distances per second towards the end, negative to start moving away from it")
+                    .changed();
+                ui.end_row();
+            }
+            Motion::Bounce { restitution, bounces } => {
+                ui.label("Restitution");
+                changed |= ui
+                    .add(DragValue::new(restitution).speed(0.01).clamp_range(0.0..=0.95))
+                    .on_hover_text("How much of its speed the ball keeps on every bounce")
+                    .changed();
+                ui.end_row();
+                ui.label("Bounces");
+                changed |= ui.add(DragValue::new(bounces).clamp_range(0..=20)).changed();
+                ui.end_row();
+            }
+        }
+        for (label, point) in [("Start", &mut generator.start), ("End", &mut generator.end)] {
+            ui.label(label);
+            ui.horizontal(|ui| {
+                changed |= ui.add(DragValue::new(&mut point.x).speed(0.01).prefix("x: ")).changed();
+                changed |= ui.add(DragValue::new(&mut point.y).speed(0.01).prefix("y: ")).changed();
+            });
+            ui.end_row();
+        }
+    });
+    if generator.end.x <= generator.start.x {
+        generator.end.x = generator.start.x + MIN_AXIS_SPAN;
+    }
+    changed
+}
+
+/// a button showing the shape of a preset, with its name on hover
+fn preset_thumbnail(ui: &mut Ui, preset: &Preset) -> Response {
+    let (rect, response) = ui.allocate_exact_size(Vec2::splat(PRESET_THUMBNAIL_SIZE), Sense::click());
+    if ui.is_rect_visible(rect) && !preset.keys.is_empty() {
+        let visuals = ui.style().interact(&response);
+        ui.painter()
+            .rect(rect, visuals.rounding, visuals.bg_fill, visuals.bg_stroke);
+
+        // fit overshooting presets like back and elastic inside the button too
+        let points = splines::flatten(&preset.keys, Some(0.002));
+        let (min, max) = points
+            .iter()
+            .fold((0.0f64, 1.0f64), |(min, max), p| (min.min(p[1]), max.max(p[1])));
+        let inner = rect.shrink(PRESET_THUMBNAIL_SIZE * 0.15);
+        let line = points
+            .iter()
+            .map(|p| {
+                pos2(
+                    inner.left() + p[0] as f32 * inner.width(),
+                    inner.bottom() - ((p[1] - min) / (max - min)) as f32 * inner.height(),
+                )
+            })
+            .collect();
+        ui.painter()
+            .add(Shape::line(line, Stroke::new(1.5, visuals.fg_stroke.color)));
+    }
+    response.on_hover_text(&preset.name)
+}
+
+/// min/max/clamp editor for a single axis. Returns true if anything changed.
+fn axis_limits_ui(ui: &mut Ui, label: &str, limits: &mut AxisLimits) -> bool {
+    let speed = (limits.span() * 0.01).max(MIN_AXIS_SPAN);
+
+    ui.label(label);
+    let mut changed = ui
+        .add(DragValue::new(&mut limits.min).speed(speed).prefix("min: "))
+        .changed();
+    changed |= ui
+        .add(DragValue::new(&mut limits.max).speed(speed).prefix("max: "))
+        .changed();
+    changed |= ui.checkbox(&mut limits.clamped, "Clamp").changed();
+
+    if limits.span() < MIN_AXIS_SPAN {
+        limits.max = limits.min + MIN_AXIS_SPAN;
+    }
+    changed
+}
+
+/// shortens `tangent` along its own direction so its `axis` component (0 is x, 1 is y) fits in `min..=max`, which must
+/// contain 0. When the limit is 0 only that component is zeroed so the tangent becomes flat (or vertical for x).
+fn fit_tangent(tangent: splines::Vec2, axis: usize, min: f64, max: f64) -> splines::Vec2 {
+    let value = tangent[axis];
+    let limit = if value > max {
+        max
+    } else if value < min {
+        min
+    } else {
+        return tangent;
+    };
+
+    if limit == 0.0 {
+        let mut tangent = tangent;
+        tangent[axis] = 0.0;
+        tangent
+    } else {
+        tangent * (limit / value)
+    }
+}
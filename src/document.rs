@@ -0,0 +1,358 @@
+use egui::Color32;
+
+use crate::fitting;
+use crate::generators::Generator;
+use crate::splines::{self, AnimationKey, Precision, Vec2};
+
+/// most channels a curve can have, for a `vec4`
+pub const MAX_CHANNELS: usize = 4;
+pub const CHANNEL_NAMES: [&str; MAX_CHANNELS] = ["x", "y", "z", "w"];
+/// what a curve with one to `MAX_CHANNELS` channels outputs
+pub const DIMENSION_NAMES: [&str; MAX_CHANNELS] = ["Scalar", "Vec2", "Vec3", "Vec4"];
+/// step of the central differences that estimate the slope of modified curves, relative to the size of x
+const SLOPE_STEP: f64 = 1e-6;
+
+/// Inclusive limits for one axis of a curve. An axis that isn't `clamped` is unbounded: keys can go anywhere
+/// and `min`/`max` only describe the default view.
+#[derive(PartialEq, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct AxisLimits {
+    pub min: f64,
+    pub max: f64,
+    pub clamped: bool,
+}
+
+impl AxisLimits {
+    pub const fn new(min: f64, max: f64) -> AxisLimits {
+        AxisLimits {
+            min,
+            max,
+            clamped: true,
+        }
+    }
+
+    pub fn span(&self) -> f64 {
+        self.max - self.min
+    }
+
+    pub fn clamp(&self, value: f64) -> f64 {
+        if self.clamped {
+            value.clamp(self.min, self.max)
+        } else {
+            value
+        }
+    }
+
+    /// the limits when clamped, otherwise the smallest interval containing the limits and all `values`
+    pub fn extents(&self, values: impl Iterator<Item = f64>) -> (f64, f64) {
+        if self.clamped {
+            return (self.min, self.max);
+        }
+        values.fold((self.min, self.max), |(min, max), v| (min.min(v), max.max(v)))
+    }
+}
+
+/// The domain (x) and range (y) a curve lives in
+#[derive(PartialEq, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CurveLimits {
+    pub domain: AxisLimits,
+    pub range: AxisLimits,
+}
+
+impl Default for CurveLimits {
+    fn default() -> Self {
+        Self {
+            domain: AxisLimits::new(0.0, 1.0),
+            range: AxisLimits::new(-1.0, 1.0),
+        }
+    }
+}
+
+impl CurveLimits {
+    pub fn clamp(&self, pos: Vec2) -> Vec2 {
+        curve::vec2(self.domain.clamp(pos.x), self.range.clamp(pos.y))
+    }
+}
+
+/// How the keys of a curve are kept in shape while they are edited
+#[derive(PartialEq, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub struct KeyConstraints {
+    /// stops keys from passing their neighbours and tangents from leaving their segment so x(t) stays monotonic
+    pub keep_keys_ordered: bool,
+    /// fits tangents so every segment stays inside the range (when the range is clamped)
+    pub prevent_overshoot: bool,
+    /// keeps every segment monotonic by adjusting tangents with the Fritsch–Carlson conditions
+    pub monotonic: bool,
+}
+
+impl Default for KeyConstraints {
+    fn default() -> Self {
+        Self {
+            keep_keys_ordered: true,
+            prevent_overshoot: false,
+            monotonic: false,
+        }
+    }
+}
+
+/// One curve of a document, like size or alpha over a particle's lifetime
+#[derive(PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct NamedCurve<T = f64> {
+    pub name: String,
+    /// colour of the curve and its legend entry
+    pub color: Color32,
+    pub visible: bool,
+    /// locked curves are drawn but their keys can't be edited
+    pub locked: bool,
+    /// keys of each channel: one for a scalar curve, 2 - 4 for a vector. Every channel has keys at the same x values
+    /// and the key at an index has the same id in every channel.
+    pub channels: Vec<Vec<curve::AnimationKey<T>>>,
+    /// applied to every channel in order by `evaluate`
+    #[serde(default)]
+    pub modifiers: Vec<curve::Modifier<T>>,
+    /// the motion the keys of every channel were generated from, which keeps them from being edited by hand
+    #[serde(default)]
+    pub generator: Option<Generator>,
+    /// domain and range the keys are edited in
+    #[serde(default)]
+    pub limits: CurveLimits,
+    #[serde(default)]
+    pub constraints: KeyConstraints,
+    /// the channel being edited
+    #[serde(skip)]
+    pub channel: usize,
+}
+
+impl NamedCurve {
+    pub fn new(name: impl Into<String>, color: Color32, keys: Vec<AnimationKey>) -> Self {
+        Self {
+            name: name.into(),
+            color,
+            visible: true,
+            locked: false,
+            channels: vec![keys],
+            modifiers: vec![],
+            generator: None,
+            limits: CurveLimits::default(),
+            constraints: KeyConstraints::default(),
+            channel: 0,
+        }
+    }
+
+    /// whether any modifier is enabled
+    pub fn has_modifiers(&self) -> bool {
+        self.modifiers.iter().any(|modifier| modifier.enabled)
+    }
+
+    /// channel `channel` at `x` with the modifiers applied
+    pub fn evaluate(&self, channel: usize, x: f64) -> f64 {
+        splines::evaluate_modified(&self.channels[channel], &self.modifiers, x)
+    }
+
+    /// dy/dx of `evaluate`, estimated from either side of `x` when modifiers are enabled
+    pub fn evaluate_derivative(&self, channel: usize, x: f64) -> f64 {
+        if !self.has_modifiers() {
+            return splines::evaluate_derivative(&self.channels[channel], x);
+        }
+        let step = SLOPE_STEP * x.abs().max(1.0);
+        (self.evaluate(channel, x + step) - self.evaluate(channel, x - step)) / (2.0 * step)
+    }
+
+    /// Replaces the keys of every channel with keys within `tolerance` of the modified curve at `samples`, at the same
+    /// x values in every channel, and removes the modifiers
+    pub fn bake_modifiers(&mut self, samples: &[f64], tolerance: f64, monotonic: bool) {
+        let modifiers = std::mem::take(&mut self.modifiers);
+        let channels = std::mem::take(&mut self.channels);
+        let functions: Vec<Box<dyn Fn(f64) -> f64 + '_>> = channels
+            .iter()
+            .map(|keys| Box::new(|x| splines::evaluate_modified(keys, &modifiers, x)) as Box<dyn Fn(f64) -> f64>)
+            .collect();
+        let functions: Vec<&dyn Fn(f64) -> f64> = functions.iter().map(|f| f.as_ref()).collect();
+        self.channels = fitting::fit_functions(&functions, samples, tolerance, monotonic);
+        // the keys are at the same x in every channel so this can't fail
+        let _ = self.link_channels();
+    }
+
+    /// Replaces the keys of every channel with keys within `tolerance` of the generator, if there is one
+    pub fn generate(&mut self, tolerance: f64) {
+        if let Some(generator) = &self.generator {
+            let keys = generator.keys(tolerance);
+            self.channels.iter_mut().for_each(|channel| *channel = keys.clone());
+        }
+    }
+
+    /// keys of the channel being edited
+    pub fn keys(&self) -> &Vec<AnimationKey> {
+        &self.channels[self.channel]
+    }
+
+    pub fn keys_mut(&mut self) -> &mut Vec<AnimationKey> {
+        &mut self.channels[self.channel]
+    }
+
+    pub fn is_vector(&self) -> bool {
+        self.channels.len() > 1
+    }
+
+    /// `name` for a scalar curve and `name.x` style names for the channels of a vector
+    pub fn channel_name(&self, channel: usize) -> String {
+        if self.is_vector() {
+            format!("{}.{}", self.name, CHANNEL_NAMES[channel])
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// Changes the number of channels. New channels start as copies of the first one, sharing its key ids.
+    pub fn set_channel_count(&mut self, count: usize) {
+        let count = count.clamp(1, MAX_CHANNELS);
+        let first = self.channels[0].clone();
+        self.channels.resize(count, first);
+        self.channel = self.channel.min(count - 1);
+    }
+
+    /// Moves the keys of every other channel to the x values of the keys in the channel being edited. Keys are matched
+    /// by id: matched keys keep their value and tangents, new keys are added on the other channels where those
+    /// channels already pass and keys removed from the edited channel are removed from all of them. Returns whether
+    /// any channel changed.
+    pub fn sync_channels(&mut self) -> bool {
+        let source = std::mem::take(&mut self.channels[self.channel]);
+        let mut changed = false;
+        for channel in &mut self.channels {
+            let in_sync = channel.len() == source.len()
+                && channel
+                    .iter()
+                    .zip(&source)
+                    .all(|(a, b)| a.id == b.id && a.pos.x == b.pos.x);
+            if source.is_empty() || in_sync {
+                continue;
+            }
+
+            changed = true;
+            let old = std::mem::take(channel);
+            *channel = source
+                .iter()
+                .map(|key| match old.iter().find(|k| k.id == key.id) {
+                    Some(k) => AnimationKey {
+                        pos: Vec2::new(key.pos.x, k.pos.y),
+                        ..k.clone()
+                    },
+                    None => sampled_key(&old, key),
+                })
+                .collect();
+        }
+        self.channels[self.channel] = source;
+        changed
+    }
+
+    /// Gives the key at each index the same id in every channel, which loaded curves need since ids aren't saved
+    pub fn link_channels(&mut self) -> Result<(), curve::ChannelError> {
+        let (first, rest) = self.channels.split_first_mut().ok_or(curve::ChannelError::Empty)?;
+        for channel in rest {
+            if channel.len() != first.len() {
+                return Err(curve::ChannelError::KeyCount);
+            }
+            for (index, (key, first_key)) in channel.iter_mut().zip(first.iter()).enumerate() {
+                if key.pos.x != first_key.pos.x {
+                    return Err(curve::ChannelError::KeyTime(index));
+                }
+                key.id = first_key.id;
+            }
+        }
+        Ok(())
+    }
+
+    /// the same curve with keys in another precision
+    fn cast<U: curve::Real>(&self) -> NamedCurve<U> {
+        NamedCurve {
+            name: self.name.clone(),
+            color: self.color,
+            visible: self.visible,
+            locked: self.locked,
+            channels: self
+                .channels
+                .iter()
+                .map(|keys| keys.iter().map(|key| key.cast()).collect())
+                .collect(),
+            modifiers: self.modifiers.iter().map(|modifier| modifier.cast()).collect(),
+            generator: self.generator.clone(),
+            limits: self.limits,
+            constraints: self.constraints,
+            channel: self.channel,
+        }
+    }
+}
+
+/// a key with `like`'s x, id and handle lengths placed on `keys` without changing its shape
+fn sampled_key(keys: &[AnimationKey], like: &AnimationKey) -> AnimationKey {
+    let x = like.pos.x;
+    let (y, slope) = if keys.is_empty() {
+        (0.0, 0.0)
+    } else {
+        (splines::evaluate(keys, x), splines::evaluate_derivative(keys, x))
+    };
+    AnimationKey {
+        pos: Vec2::new(x, y),
+        tangent_in: Vec2::new(like.tangent_in.x, like.tangent_in.x * slope),
+        tangent_out: Vec2::new(like.tangent_out.x, like.tangent_out.x * slope),
+        ..like.clone()
+    }
+}
+
+/// The curves as RON with keys at `precision`. Runtimes only need the `name` and `channels` fields, plus `modifiers`
+/// for `curve::evaluate_modified`, and can leave the rest out of the struct they load into.
+pub fn to_ron(curves: &[NamedCurve], precision: Precision) -> String {
+    let config = ron::ser::PrettyConfig::default();
+    match precision {
+        Precision::Single => {
+            let curves: Vec<NamedCurve<f32>> = curves.iter().map(NamedCurve::cast).collect();
+            ron::ser::to_string_pretty(&curves, config)
+        }
+        Precision::Double => ron::ser::to_string_pretty(curves, config),
+    }
+    .unwrap_or_default()
+}
+
+/// Why a document could not be read
+#[derive(Debug)]
+pub enum ReadError {
+    Ron(ron::error::SpannedError),
+    /// the channels of the named curve don't line up
+    Channels(String, curve::ChannelError),
+    /// a key of the named curve has a NaN or infinite position or tangent
+    NonFinite(String),
+}
+
+impl std::fmt::Display for ReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReadError::Ron(err) => write!(f, "{err}"),
+            ReadError::Channels(name, err) => write!(f, "{name}: {err}"),
+            ReadError::NonFinite(name) => write!(f, "{name}: a key is not a finite number"),
+        }
+    }
+}
+
+/// Reads curves written by `to_ron`
+pub fn from_ron(text: &str) -> Result<Vec<NamedCurve>, ReadError> {
+    let mut curves = ron::from_str::<Vec<NamedCurve>>(text).map_err(ReadError::Ron)?;
+
+    for curve in &mut curves {
+        curve.channels.truncate(MAX_CHANNELS);
+        let finite = |v: Vec2| v.x.is_finite() && v.y.is_finite();
+        if !curve
+            .channels
+            .iter()
+            .flatten()
+            .all(|key| finite(key.pos) && finite(key.tangent_in) && finite(key.tangent_out))
+        {
+            return Err(ReadError::NonFinite(curve.name.clone()));
+        }
+        for keys in &mut curve.channels {
+            keys.sort_by(|a, b| a.pos.x.total_cmp(&b.pos.x));
+        }
+        curve
+            .link_channels()
+            .map_err(|err| ReadError::Channels(curve.name.clone(), err))?;
+    }
+    Ok(curves)
+}
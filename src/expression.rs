@@ -0,0 +1,508 @@
+use std::f64::consts::{E, PI, TAU};
+
+/// Why an expression could not be parsed
+#[derive(Debug, PartialEq, Clone)]
+pub struct ExpressionError {
+    /// 1 based character the problem starts at
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+/// Functions expressions can call
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Ln,
+    Log10,
+    Log2,
+    Sqrt,
+    Cbrt,
+    Abs,
+    Sign,
+    Floor,
+    Ceil,
+    Round,
+    Fract,
+    Min,
+    Max,
+    Pow,
+    Atan2,
+    Step,
+    Clamp,
+    Mix,
+    Smoothstep,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        Some(match name {
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "asin" => Function::Asin,
+            "acos" => Function::Acos,
+            "atan" => Function::Atan,
+            "sinh" => Function::Sinh,
+            "cosh" => Function::Cosh,
+            "tanh" => Function::Tanh,
+            "exp" => Function::Exp,
+            "ln" | "log" => Function::Ln,
+            "log10" => Function::Log10,
+            "log2" => Function::Log2,
+            "sqrt" => Function::Sqrt,
+            "cbrt" => Function::Cbrt,
+            "abs" => Function::Abs,
+            "sign" => Function::Sign,
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            "round" => Function::Round,
+            "fract" => Function::Fract,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "pow" => Function::Pow,
+            "atan2" => Function::Atan2,
+            "step" => Function::Step,
+            "clamp" => Function::Clamp,
+            "mix" | "lerp" => Function::Mix,
+            "smoothstep" => Function::Smoothstep,
+            _ => return None,
+        })
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Function::Min | Function::Max | Function::Pow | Function::Atan2 | Function::Step => 2,
+            Function::Clamp | Function::Mix | Function::Smoothstep => 3,
+            _ => 1,
+        }
+    }
+
+    /// `args` starts with `arity()` values
+    fn call(self, args: &[f64]) -> f64 {
+        let a = args[0];
+        match self {
+            Function::Sin => a.sin(),
+            Function::Cos => a.cos(),
+            Function::Tan => a.tan(),
+            Function::Asin => a.asin(),
+            Function::Acos => a.acos(),
+            Function::Atan => a.atan(),
+            Function::Sinh => a.sinh(),
+            Function::Cosh => a.cosh(),
+            Function::Tanh => a.tanh(),
+            Function::Exp => a.exp(),
+            Function::Ln => a.ln(),
+            Function::Log10 => a.log10(),
+            Function::Log2 => a.log2(),
+            Function::Sqrt => a.sqrt(),
+            Function::Cbrt => a.cbrt(),
+            Function::Abs => a.abs(),
+            Function::Sign if a == 0.0 => 0.0,
+            Function::Sign => a.signum(),
+            Function::Floor => a.floor(),
+            Function::Ceil => a.ceil(),
+            Function::Round => a.round(),
+            Function::Fract => a - a.floor(),
+            Function::Min => a.min(args[1]),
+            Function::Max => a.max(args[1]),
+            Function::Pow => a.powf(args[1]),
+            Function::Atan2 => a.atan2(args[1]),
+            // like GLSL, step(edge, x)
+            Function::Step => {
+                if args[1] < a {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Function::Clamp => a.max(args[1]).min(args[2]),
+            Function::Mix => a + (args[1] - a) * args[2],
+            Function::Smoothstep => {
+                let t = ((args[2] - a) / (args[1] - a)).clamp(0.0, 1.0);
+                t * t * (3.0 - 2.0 * t)
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Power,
+}
+
+#[derive(PartialEq, Clone, Debug)]
+enum Node {
+    Number(f64),
+    X,
+    Negate(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+impl Node {
+    fn evaluate(&self, x: f64) -> f64 {
+        match self {
+            Node::Number(value) => *value,
+            Node::X => x,
+            Node::Negate(node) => -node.evaluate(x),
+            Node::Binary(operator, a, b) => {
+                let (a, b) = (a.evaluate(x), b.evaluate(x));
+                match operator {
+                    Operator::Add => a + b,
+                    Operator::Subtract => a - b,
+                    Operator::Multiply => a * b,
+                    Operator::Divide => a / b,
+                    Operator::Remainder => a % b,
+                    Operator::Power => a.powf(b),
+                }
+            }
+            Node::Call(function, args) => {
+                // no function takes more than three arguments
+                let mut values = [0.0; 3];
+                for (value, arg) in values.iter_mut().zip(args) {
+                    *value = arg.evaluate(x);
+                }
+                function.call(&values)
+            }
+        }
+    }
+}
+
+/// A formula of `x` like `1 - exp(-4x)` or `sin(x*PI)^2`, parsed once so it can be evaluated quickly.
+///
+/// Expressions have `+ - * / %`, `^` (or `**`) for powers, which binds tighter than a leading minus so `-x^2` is
+/// `-(x^2)`, and multiplication can be implied as in `4x` or `2 sin(x)`. `t` is another name for `x` and the constants
+/// are `pi`, `tau` and `e`. The functions are sin, cos, tan, asin, acos, atan, sinh, cosh, tanh, exp, ln (or log),
+/// log10, log2, sqrt, cbrt, abs, sign, floor, ceil, round, fract, min, max, pow, atan2, and GLSL's step, clamp, mix
+/// and smoothstep. Names are not case sensitive.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Expression {
+    root: Node,
+}
+
+impl Expression {
+    pub fn parse(text: &str) -> Result<Expression, ExpressionError> {
+        let mut parser = Parser {
+            chars: text.chars().collect(),
+            index: 0,
+            depth: 0,
+        };
+        let root = parser.expression()?;
+        parser.skip_whitespace();
+        if parser.index < parser.chars.len() {
+            return Err(parser.error(format!("unexpected '{}'", parser.chars[parser.index])));
+        }
+        Ok(Expression { root })
+    }
+
+    /// the value at `x`, which is NaN or infinite wherever the formula is undefined
+    pub fn evaluate(&self, x: f64) -> f64 {
+        self.root.evaluate(x)
+    }
+}
+
+/// how deeply brackets, arguments, powers and signs can nest before parsing gives up rather than overflow the stack
+const MAX_DEPTH: usize = 64;
+
+/// recursive descent over the characters of an expression
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    /// calls to `unary` in progress, which every nested part of an expression goes through
+    depth: usize,
+}
+
+impl Parser {
+    fn error(&self, message: impl Into<String>) -> ExpressionError {
+        ExpressionError {
+            column: self.index + 1,
+            message: message.into(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.index).map_or(false, |c| c.is_whitespace()) {
+            self.index += 1;
+        }
+    }
+
+    /// the next character that isn't whitespace, without consuming it
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.get(self.index).copied()
+    }
+
+    /// consumes `c` if it is next
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// sums and differences of terms
+    fn expression(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.term()?;
+        loop {
+            let operator = if self.eat('+') {
+                Operator::Add
+            } else if self.eat('-') {
+                Operator::Subtract
+            } else {
+                return Ok(node);
+            };
+            node = Node::Binary(operator, Box::new(node), Box::new(self.term()?));
+        }
+    }
+
+    /// products, quotients and remainders, including multiplication implied by a name or bracket like `4x`
+    fn term(&mut self) -> Result<Node, ExpressionError> {
+        let mut node = self.unary()?;
+        loop {
+            let operator = match self.peek() {
+                Some('*') if self.chars.get(self.index + 1) != Some(&'*') => {
+                    self.index += 1;
+                    Operator::Multiply
+                }
+                Some('/') => {
+                    self.index += 1;
+                    Operator::Divide
+                }
+                Some('%') => {
+                    self.index += 1;
+                    Operator::Remainder
+                }
+                Some(c) if c.is_alphabetic() || c == '(' => Operator::Multiply,
+                _ => return Ok(node),
+            };
+            node = Node::Binary(operator, Box::new(node), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, ExpressionError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("too deeply nested"));
+        }
+        self.depth += 1;
+        let node = self.signed();
+        self.depth -= 1;
+        node
+    }
+
+    /// a power with any number of leading signs
+    fn signed(&mut self) -> Result<Node, ExpressionError> {
+        if self.eat('-') {
+            Ok(Node::Negate(Box::new(self.unary()?)))
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    /// `^` is right associative and its exponent can have a sign, so `2^-x^2` is `2^(-(x^2))`
+    fn power(&mut self) -> Result<Node, ExpressionError> {
+        let base = self.atom()?;
+        let is_power = match self.peek() {
+            Some('^') => {
+                self.index += 1;
+                true
+            }
+            Some('*') if self.chars.get(self.index + 1) == Some(&'*') => {
+                self.index += 2;
+                true
+            }
+            _ => false,
+        };
+        if is_power {
+            Ok(Node::Binary(Operator::Power, Box::new(base), Box::new(self.unary()?)))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node, ExpressionError> {
+        match self.peek() {
+            Some('(') => {
+                self.index += 1;
+                let node = self.expression()?;
+                if !self.eat(')') {
+                    return Err(self.error("expected ')'"));
+                }
+                Ok(node)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_alphabetic() => self.name(),
+            Some(c) => Err(self.error(format!("unexpected '{c}'"))),
+            None => Err(self.error("expected a value")),
+        }
+    }
+
+    /// digits with an optional fraction and exponent, like `2`, `.5` or `1.5e-3`
+    fn number(&mut self) -> Result<Node, ExpressionError> {
+        let start = self.index;
+        let digits = |parser: &mut Parser| {
+            while parser.chars.get(parser.index).map_or(false, |c| c.is_ascii_digit()) {
+                parser.index += 1;
+            }
+        };
+        digits(self);
+        if self.chars.get(self.index) == Some(&'.') {
+            self.index += 1;
+            digits(self);
+        }
+        // only an exponent if digits follow, otherwise `2e` is 2 times e
+        if matches!(self.chars.get(self.index), Some('e' | 'E')) {
+            let sign = matches!(self.chars.get(self.index + 1), Some('+' | '-')) as usize;
+            if self
+                .chars
+                .get(self.index + 1 + sign)
+                .map_or(false, |c| c.is_ascii_digit())
+            {
+                self.index += 1 + sign;
+                digits(self);
+            }
+        }
+
+        let text: String = self.chars[start..self.index].iter().collect();
+        text.parse().map(Node::Number).map_err(|_| ExpressionError {
+            column: start + 1,
+            message: format!("'{text}' is not a number"),
+        })
+    }
+
+    /// a variable, constant or function call
+    fn name(&mut self) -> Result<Node, ExpressionError> {
+        let start = self.index;
+        while self
+            .chars
+            .get(self.index)
+            .map_or(false, |c| c.is_alphanumeric() || *c == '_')
+        {
+            self.index += 1;
+        }
+        let name = self.chars[start..self.index].iter().collect::<String>().to_lowercase();
+        let unknown = |message: String| ExpressionError {
+            column: start + 1,
+            message,
+        };
+
+        match name.as_str() {
+            "x" | "t" => return Ok(Node::X),
+            "pi" => return Ok(Node::Number(PI)),
+            "tau" => return Ok(Node::Number(TAU)),
+            "e" => return Ok(Node::Number(E)),
+            _ => {}
+        }
+        let function = Function::from_name(&name).ok_or_else(|| unknown(format!("unknown name '{name}'")))?;
+        if !self.eat('(') {
+            return Err(self.error(format!("expected '(' after {name}")));
+        }
+        let mut args = vec![self.expression()?];
+        while self.eat(',') {
+            args.push(self.expression()?);
+        }
+        if !self.eat(')') {
+            return Err(self.error("expected ')'"));
+        }
+        if args.len() != function.arity() {
+            return Err(unknown(format!(
+                "{name} takes {} argument{} but got {}",
+                function.arity(),
+                if function.arity() == 1 { "" } else { "s" },
+                args.len()
+            )));
+        }
+        Ok(Node::Call(function, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(text: &str, x: f64) -> f64 {
+        Expression::parse(text).unwrap().evaluate(x)
+    }
+
+    fn error(text: &str) -> ExpressionError {
+        Expression::parse(text).unwrap_err()
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(evaluate("-x^2", 3.0), -9.0);
+        assert_eq!(evaluate("2^-x^2", 1.0), 0.5);
+        assert_eq!(evaluate("2^-x^2", 2.0), 1.0 / 16.0);
+        assert_eq!(evaluate("2^3^2", 0.0), 512.0);
+        assert_eq!(evaluate("1 - 2 - 3", 0.0), -4.0);
+        assert_eq!(evaluate("8 / 2 / 2", 0.0), 2.0);
+        assert!((evaluate("sin(x*PI)^2", 0.5) - 1.0).abs() < 1e-12);
+        assert!((evaluate("sin(x*PI)^2", 0.25) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn implied_multiplication() {
+        assert_eq!(evaluate("4x", 2.0), 8.0);
+        assert_eq!(evaluate("2 sin(x)", 0.0), 0.0);
+        assert_eq!(evaluate("2(x + 1)", 1.0), 4.0);
+        assert_eq!(evaluate("-4x^2", 2.0), -16.0);
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(evaluate("2e", 0.0), 2.0 * E);
+        assert_eq!(evaluate("1e-3", 0.0), 1e-3);
+        assert_eq!(evaluate("1.5E+2", 0.0), 150.0);
+        assert_eq!(evaluate(".5", 0.0), 0.5);
+    }
+
+    #[test]
+    fn error_positions() {
+        assert_eq!(error("1 +").column, 4);
+        assert_eq!(error("2 $ 3").column, 3);
+        assert_eq!(error("1 + foo(x)").column, 5);
+        assert_eq!(error("sin(x").column, 6);
+        assert_eq!(error("sin x").column, 5);
+        assert_eq!(error("min(1)").column, 1);
+        assert_eq!(error("(x))").column, 4);
+        assert_eq!(error(".").column, 1);
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let nested = |depth: usize| format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(evaluate(&nested(MAX_DEPTH - 1), 2.0), 2.0);
+        assert_eq!(error(&nested(MAX_DEPTH)).column, MAX_DEPTH + 1);
+        assert_eq!(error(&nested(100_000)).column, MAX_DEPTH + 1);
+
+        assert_eq!(evaluate(&format!("{}x", "-".repeat(MAX_DEPTH - 1)), 2.0), -2.0);
+        assert_eq!(error(&format!("{}x", "-".repeat(100_000))).column, MAX_DEPTH + 1);
+        assert!(Expression::parse(&format!("{}x", "2^".repeat(100_000))).is_err());
+        assert!(Expression::parse(&format!("{}x{}", "sin(".repeat(100_000), ")".repeat(100_000))).is_err());
+    }
+}
@@ -0,0 +1,588 @@
+use std::ops::RangeInclusive;
+
+use curve::BakedCurve;
+
+use crate::splines::{self, AnimationKey, InverseError, Vec2};
+
+/// fitting gives up refining once it has this many keys
+const MAX_FIT_KEYS: usize = 256;
+/// most keys a least squares fit to samples will try when fitting to a tolerance
+pub const MAX_SAMPLE_FIT_KEYS: usize = 64;
+/// weight of the smoothness term that keeps least squares fits solvable when some segments have no samples
+const SMOOTHING: f64 = 1e-6;
+/// evenly spaced samples added on top of the flattened curve when measuring fitting error
+const FIT_GRID_SAMPLES: usize = 512;
+/// fewest chebyshev nodes a polynomial is fitted to
+const POLYNOMIAL_SAMPLES: usize = 256;
+/// highest polynomial degree offered. Past this the coefficients need more precision than f32 shader code has.
+pub const MAX_POLYNOMIAL_DEGREE: usize = 16;
+/// step used for the one-sided differences that estimate slopes, as a fraction of the domain
+const SLOPE_STEP: f64 = 1e-4;
+
+/// A point on the function being fitted along with the slopes arriving at and leaving it
+#[derive(Clone, Copy)]
+struct FitNode {
+    x: f64,
+    y: f64,
+    slope_in: f64,
+    slope_out: f64,
+}
+
+/// Fits keys to `f` so that `splines::evaluate` on them is within `tolerance` of `f` at every x in `samples`, which
+/// must be sorted and span the domain to fit. Keys are placed at the worst sample of every segment that is out of
+/// tolerance, then any key the curve can do without is removed again. Slopes come from `f` itself and handles are a
+/// third of the segment width so every segment is the cubic hermite between its keys. `monotonic` runs
+/// `splines::make_monotonic` on the keys as they are built.
+pub fn fit_function(f: impl Fn(f64) -> f64, samples: &[f64], tolerance: f64, monotonic: bool) -> Vec<AnimationKey> {
+    let first = samples[0];
+    let last = samples[samples.len() - 1];
+    let step = (last - first) * SLOPE_STEP;
+    let targets: Vec<f64> = samples.iter().map(|&x| f(x)).collect();
+
+    let node = |x: f64| fit_node(&f, x, first..=last, step);
+
+    let mut nodes = vec![node(first)];
+    if last > first {
+        nodes.push(node(last));
+    }
+
+    // refine: the worst sample of every segment that is out of tolerance becomes a new key
+    while nodes.len() < MAX_FIT_KEYS {
+        let keys = build_keys(&nodes, monotonic);
+        // keys closer together than the slope step can't be told apart so they won't help
+        let worst: Vec<f64> = keys
+            .windows(2)
+            .filter_map(|pair| worst_sample(pair, samples, &targets, tolerance))
+            .filter(|&x| nodes.iter().all(|node| (node.x - x).abs() > step))
+            .collect();
+
+        // stop once no segment needs (or can take) refining
+        if worst.is_empty() {
+            break;
+        }
+        nodes.extend(worst.into_iter().map(node));
+        nodes.sort_by(|a, b| a.x.total_cmp(&b.x));
+    }
+
+    // prune: drop any key whose neighbours can cover its stretch on their own
+    let mut i = 1;
+    while i + 1 < nodes.len() {
+        let window: Vec<FitNode> = nodes[i.saturating_sub(2)..(i + 3).min(nodes.len())]
+            .iter()
+            .filter(|n| n.x != nodes[i].x)
+            .copied()
+            .collect();
+        let keys = build_keys(&window, monotonic);
+        let fits = keys.windows(2).all(|pair| {
+            pair[1].pos.x <= nodes[i - 1].x
+                || pair[0].pos.x >= nodes[i + 1].x
+                || worst_sample(pair, samples, &targets, tolerance).is_none()
+        });
+
+        if fits {
+            nodes.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+
+    build_keys(&nodes, monotonic)
+}
+
+/// `f` at `x` with its slopes from one sided differences that stay inside `domain`
+fn fit_node(f: impl Fn(f64) -> f64, x: f64, domain: RangeInclusive<f64>, step: f64) -> FitNode {
+    let y = f(x);
+    let slope_out = if x + step <= *domain.end() {
+        Some((f(x + step) - y) / step)
+    } else {
+        None
+    };
+    let slope_in = if x - step >= *domain.start() {
+        Some((y - f(x - step)) / step)
+    } else {
+        None
+    };
+    FitNode {
+        x,
+        y,
+        slope_in: slope_in.or(slope_out).unwrap_or(0.0),
+        slope_out: slope_out.or(slope_in).unwrap_or(0.0),
+    }
+}
+
+/// `fit_function` for every function in `functions` with the keys of all of them at the same x values, so they can be
+/// the channels of a vector curve. Each function gets a key wherever any of them needed one.
+pub fn fit_functions(
+    functions: &[&dyn Fn(f64) -> f64],
+    samples: &[f64],
+    tolerance: f64,
+    monotonic: bool,
+) -> Vec<Vec<AnimationKey>> {
+    if let [f] = functions {
+        return vec![fit_function(f, samples, tolerance, monotonic)];
+    }
+
+    let mut xs: Vec<f64> = functions
+        .iter()
+        .flat_map(|f| fit_function(f, samples, tolerance, monotonic))
+        .map(|key| key.pos.x)
+        .collect();
+    sort_samples(&mut xs);
+
+    let (first, last) = (samples[0], samples[samples.len() - 1]);
+    let step = (last - first) * SLOPE_STEP;
+    functions
+        .iter()
+        .map(|f| {
+            let nodes: Vec<FitNode> = xs.iter().map(|&x| fit_node(f, x, first..=last, step)).collect();
+            build_keys(&nodes, monotonic)
+        })
+        .collect()
+}
+
+/// Keys through `points` of x, y, slope in and slope out with handles a third of the way to each neighbour, so every
+/// segment is the cubic hermite between its keys and any piecewise cubic is reproduced exactly. `points` must be
+/// sorted by x.
+pub fn hermite_keys(points: &[[f64; 4]]) -> Vec<AnimationKey> {
+    let nodes: Vec<FitNode> = points
+        .iter()
+        .map(|&[x, y, slope_in, slope_out]| FitNode {
+            x,
+            y,
+            slope_in,
+            slope_out,
+        })
+        .collect();
+    build_keys(&nodes, false)
+}
+
+/// Reduces `points` to the fewest keys that stay within `tolerance` of the original curve. The error is measured at
+/// the flattened curve plus an even grid across the keys.
+pub fn reduce_keys(points: &[AnimationKey], tolerance: f64, monotonic: bool) -> Vec<AnimationKey> {
+    let samples = curve_samples(points, tolerance);
+    fit_function(|x| splines::evaluate(points, x), &samples, tolerance, monotonic)
+}
+
+/// Builds keys approximating the inverse of a monotonic curve, mapping values back to times.
+pub fn invert(points: &[AnimationKey], tolerance: f64) -> Result<Vec<AnimationKey>, InverseError> {
+    if points.is_empty() {
+        return Err(InverseError::Empty);
+    }
+    if !splines::curve_is_monotonic(points) {
+        return Err(InverseError::NotMonotonic);
+    }
+    if points[0].pos.y == points[points.len() - 1].pos.y {
+        return Err(InverseError::Constant);
+    }
+
+    // the values of the original curve are the times of the inverse
+    let inverse_points: Vec<AnimationKey> = points
+        .iter()
+        .map(|pt| AnimationKey::new(Vec2::new(pt.pos.y, pt.pos.x), 0.0))
+        .collect();
+    // flat stretches of the curve turn into steep ones in the inverse so also sample at the values of an even grid of
+    // times, which packs samples in where the inverse changes quickly
+    let mut samples = curve_samples(&inverse_points, tolerance);
+    samples.extend(
+        curve_samples(points, tolerance)
+            .iter()
+            .map(|&x| splines::evaluate(points, x)),
+    );
+    sort_samples(&mut samples);
+
+    Ok(fit_function(
+        |value| splines::evaluate_inverse(points, value).unwrap_or_default(),
+        &samples,
+        tolerance,
+        true,
+    ))
+}
+
+/// How far a curve is from a set of samples
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Residual {
+    pub max: f64,
+    /// root mean square
+    pub rms: f64,
+}
+
+pub fn residual(points: &[AnimationKey], samples: &[[f64; 2]]) -> Residual {
+    if points.is_empty() || samples.is_empty() {
+        return Residual::default();
+    }
+
+    let baked = BakedCurve::new(points);
+    let mut max = 0.0f64;
+    let mut sum_sq = 0.0f64;
+    for [x, y] in samples {
+        let error = (baked.evaluate(*x) - y).abs();
+        max = max.max(error);
+        sum_sq += error.powi(2);
+    }
+
+    Residual {
+        max,
+        rms: (sum_sq / samples.len() as f64).sqrt(),
+    }
+}
+
+/// Why keys could not be fitted to samples
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum FitError {
+    NoSamples,
+    /// every sample is at the same x, so they have no shape to fit
+    SameX,
+}
+
+impl std::fmt::Display for FitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FitError::NoSamples => write!(f, "there are no samples"),
+            FitError::SameX => write!(f, "every sample is at the same x"),
+        }
+    }
+}
+
+/// Least squares fit of `key_count` evenly spaced keys to `samples`, which must be sorted by x. The value and slope
+/// of every key are solved for together so the result is a smooth curve of hermite segments. Fewer than 2 keys is a
+/// single key at the mean value.
+pub fn fit_samples(samples: &[[f64; 2]], key_count: usize) -> Result<Vec<AnimationKey>, FitError> {
+    let Some((first, last)) = samples.first().zip(samples.last()).map(|(a, b)| (a[0], b[0])) else {
+        return Err(FitError::NoSamples);
+    };
+    if last <= first {
+        return Err(FitError::SameX);
+    }
+    if key_count < 2 {
+        let mean = samples.iter().map(|s| s[1]).sum::<f64>() / samples.len() as f64;
+        return Ok(vec![AnimationKey::new(Vec2::new(first, mean), 0.0)]);
+    }
+
+    let segments = key_count - 1;
+    let width = (last - first) / segments as f64;
+    let unknowns = 2 * key_count;
+
+    // normal equations for [value_0, slope_0, value_1, slope_1, ...]
+    let mut ata = vec![0.0f64; unknowns * unknowns];
+    let mut atb = vec![0.0f64; unknowns];
+    for &[x, y] in samples {
+        let segment = (((x - first) / width) as usize).min(segments - 1);
+        let t = (x - (first + segment as f64 * width)) / width;
+        let (t2, t3) = (t * t, t * t * t);
+        let h = width;
+        let basis = [
+            2.0 * t3 - 3.0 * t2 + 1.0,
+            (t3 - 2.0 * t2 + t) * h,
+            -2.0 * t3 + 3.0 * t2,
+            (t3 - t2) * h,
+        ];
+
+        let base = 2 * segment;
+        for (i, bi) in basis.iter().enumerate() {
+            atb[base + i] += bi * y;
+            for (j, bj) in basis.iter().enumerate() {
+                ata[(base + i) * unknowns + base + j] += bi * bj;
+            }
+        }
+    }
+
+    // penalise bends in the values, changes in slope between neighbouring keys and slopes away from the chord of their
+    // segments, which pins down keys that have no samples nearby without noticeably pulling on the rest
+    let weight = SMOOTHING * samples.len() as f64;
+    let h = width;
+    for k in 0..segments {
+        add_penalty(&mut ata, unknowns, &[(2 * k + 1, 1.0), (2 * k + 3, -1.0)], weight);
+        for slope in [2 * k + 1, 2 * k + 3] {
+            add_penalty(
+                &mut ata,
+                unknowns,
+                &[(slope, h), (2 * k, 1.0), (2 * k + 2, -1.0)],
+                weight,
+            );
+        }
+        if k + 1 < segments {
+            add_penalty(
+                &mut ata,
+                unknowns,
+                &[(2 * k, 1.0), (2 * k + 2, -2.0), (2 * k + 4, 1.0)],
+                weight,
+            );
+        }
+    }
+
+    let solution = solve(ata, atb).unwrap_or_else(|| vec![0.0; unknowns]);
+
+    let mut keys: Vec<AnimationKey> = (0..key_count)
+        .map(|k| {
+            let x = first + k as f64 * width;
+            let slope = solution[2 * k + 1];
+            let mut key = AnimationKey::new(Vec2::new(x, solution[2 * k]), width / 3.0);
+            key.tangent_in.y = -slope * width / 3.0;
+            key.tangent_out.y = slope * width / 3.0;
+            key
+        })
+        .collect();
+    keys[key_count - 1].pos.x = last;
+    Ok(keys)
+}
+
+/// The fewest evenly spaced keys whose least squares fit to `samples` is within `tolerance` everywhere, trying up to
+/// `max_keys`. Returns the best fit tried if none are within tolerance.
+pub fn fit_samples_to_tolerance(
+    samples: &[[f64; 2]],
+    tolerance: f64,
+    max_keys: usize,
+) -> Result<Vec<AnimationKey>, FitError> {
+    let mut best: Option<(f64, Vec<AnimationKey>)> = None;
+    for key_count in 2..=max_keys.max(2) {
+        let keys = fit_samples(samples, key_count)?;
+        let max = residual(&keys, samples).max;
+        if max <= tolerance {
+            return Ok(keys);
+        }
+        if best.as_ref().map_or(true, |(best_max, _)| max < *best_max) {
+            best = Some((max, keys));
+        }
+    }
+    Ok(best.map(|(_, keys)| keys).unwrap_or_default())
+}
+
+/// A single polynomial approximating a curve across a domain. It is written in terms of `u`, the domain remapped to
+/// -1..1, which keeps high degree coefficients well conditioned whatever the domain is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polynomial {
+    pub domain: RangeInclusive<f64>,
+    /// power basis coefficients in `u`, lowest degree first
+    pub coefficients: Vec<f64>,
+}
+
+impl Polynomial {
+    /// maps `x` from the domain to -1..1
+    pub fn u(&self, x: f64) -> f64 {
+        let (start, end) = (*self.domain.start(), *self.domain.end());
+        if end <= start {
+            return 0.0;
+        }
+        (2.0 * x - start - end) / (end - start)
+    }
+
+    pub fn evaluate(&self, x: f64) -> f64 {
+        let u = self.u(x);
+        self.coefficients.iter().rev().fold(0.0, |y, c| y * u + c)
+    }
+}
+
+/// Least squares fit of a degree `degree` polynomial to the curve across `domain`. Samples sit at Chebyshev nodes,
+/// which keeps the error close to the best possible (minimax) fit instead of letting it blow up near the ends.
+pub fn fit_polynomial(points: &[AnimationKey], domain: RangeInclusive<f64>, degree: usize) -> Polynomial {
+    let mut polynomial = Polynomial {
+        domain: domain.clone(),
+        coefficients: vec![0.0; degree + 1],
+    };
+    if points.is_empty() {
+        return polynomial;
+    }
+    let baked = BakedCurve::new(points);
+    let (start, end) = (*domain.start(), *domain.end());
+    if end <= start || degree == 0 {
+        let mean = (0..=FIT_GRID_SAMPLES)
+            .map(|i| baked.evaluate(start + (end - start) * i as f64 / FIT_GRID_SAMPLES as f64))
+            .sum::<f64>()
+            / (FIT_GRID_SAMPLES + 1) as f64;
+        polynomial.coefficients[0] = mean;
+        return polynomial;
+    }
+
+    // solve in the chebyshev basis, which is well conditioned on -1..1, then convert to the power basis
+    let terms = degree + 1;
+    let node_count = (8 * terms).max(POLYNOMIAL_SAMPLES);
+    let mut ata = vec![0.0f64; terms * terms];
+    let mut atb = vec![0.0f64; terms];
+    let mut basis = vec![0.0f64; terms];
+    for i in 0..node_count {
+        let u = (std::f64::consts::PI * (i as f64 + 0.5) / node_count as f64).cos();
+        let x = start + (u + 1.0) * 0.5 * (end - start);
+        let y = baked.evaluate(x);
+
+        chebyshev_basis(u, &mut basis);
+        for (r, br) in basis.iter().enumerate() {
+            atb[r] += br * y;
+            for (c, bc) in basis.iter().enumerate() {
+                ata[r * terms + c] += br * bc;
+            }
+        }
+    }
+    let Some(chebyshev) = solve(ata, atb) else {
+        return polynomial;
+    };
+
+    // power basis coefficients of T_k, built with T_k = 2u T_k-1 - T_k-2
+    let mut previous = vec![0.0f64; terms];
+    let mut current = vec![0.0f64; terms];
+    previous[0] = 1.0;
+    if terms > 1 {
+        current[1] = 1.0;
+    }
+    for (k, weight) in chebyshev.iter().enumerate() {
+        let t = match k {
+            0 => &previous,
+            1 => &current,
+            _ => {
+                let mut next = vec![0.0f64; terms];
+                for j in 1..terms {
+                    next[j] = 2.0 * current[j - 1];
+                }
+                for (n, p) in next.iter_mut().zip(&previous) {
+                    *n -= p;
+                }
+                previous = std::mem::replace(&mut current, next);
+                &current
+            }
+        };
+        for (c, t) in polynomial.coefficients.iter_mut().zip(t) {
+            *c += weight * t;
+        }
+    }
+    polynomial
+}
+
+/// largest difference between the polynomial and the curve it was fitted to, checked on an even grid and at every key
+pub fn polynomial_error(points: &[AnimationKey], polynomial: &Polynomial) -> f64 {
+    if points.is_empty() {
+        return 0.0;
+    }
+    let baked = BakedCurve::new(points);
+    let (start, end) = (*polynomial.domain.start(), *polynomial.domain.end());
+    (0..=FIT_GRID_SAMPLES)
+        .map(|i| start + (end - start) * i as f64 / FIT_GRID_SAMPLES as f64)
+        .chain(points.iter().map(|p| p.pos.x).filter(|x| polynomial.domain.contains(x)))
+        .map(|x| (polynomial.evaluate(x) - baked.evaluate(x)).abs())
+        .fold(0.0, f64::max)
+}
+
+/// fills `basis` with the chebyshev polynomials T_0(u) to T_n(u)
+fn chebyshev_basis(u: f64, basis: &mut [f64]) {
+    for k in 0..basis.len() {
+        basis[k] = match k {
+            0 => 1.0,
+            1 => u,
+            _ => 2.0 * u * basis[k - 1] - basis[k - 2],
+        };
+    }
+}
+
+/// adds `weight * (sum of coefficient * unknown)^2` to the normal equations
+fn add_penalty(ata: &mut [f64], unknowns: usize, terms: &[(usize, f64)], weight: f64) {
+    for &(i, ci) in terms {
+        for &(j, cj) in terms {
+            ata[i * unknowns + j] += weight * ci * cj;
+        }
+    }
+}
+
+/// solves the square system `matrix * x = rhs` with gaussian elimination and partial pivoting
+fn solve(mut matrix: Vec<f64>, mut rhs: Vec<f64>) -> Option<Vec<f64>> {
+    let n = rhs.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&a, &b| matrix[a * n + col].abs().total_cmp(&matrix[b * n + col].abs()))?;
+        // NaN pivots come from NaN samples and can't be eliminated with either
+        let pivot_size = matrix[pivot * n + col].abs();
+        if pivot_size < 1e-12 || pivot_size.is_nan() {
+            return None;
+        }
+        if pivot != col {
+            for k in 0..n {
+                matrix.swap(pivot * n + k, col * n + k);
+            }
+            rhs.swap(pivot, col);
+        }
+
+        for row in col + 1..n {
+            let factor = matrix[row * n + col] / matrix[col * n + col];
+            if factor != 0.0 {
+                for k in col..n {
+                    matrix[row * n + k] -= factor * matrix[col * n + k];
+                }
+                rhs[row] -= factor * rhs[col];
+            }
+        }
+    }
+
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| matrix[row * n + k] * x[k]).sum();
+        x[row] = (rhs[row] - sum) / matrix[row * n + row];
+    }
+    Some(x)
+}
+
+/// x values to measure the error of a fit against `points` at: the key times, the flattened curve and an even grid
+fn curve_samples(points: &[AnimationKey], tolerance: f64) -> Vec<f64> {
+    let first = points.iter().map(|pt| pt.pos.x).fold(f64::MAX, f64::min);
+    let last = points.iter().map(|pt| pt.pos.x).fold(f64::MIN, f64::max);
+
+    let mut samples: Vec<f64> = points.iter().map(|pt| pt.pos.x).collect();
+    samples.extend((0..=FIT_GRID_SAMPLES).map(|i| first + (last - first) * i as f64 / FIT_GRID_SAMPLES as f64));
+    samples.extend(
+        splines::flatten(points, Some(tolerance as f32))
+            .iter()
+            .map(|pt| pt[0])
+            .filter(|x| (first..=last).contains(x)),
+    );
+    sort_samples(&mut samples);
+    samples
+}
+
+fn sort_samples(samples: &mut Vec<f64>) {
+    samples.sort_by(f64::total_cmp);
+    samples.dedup();
+}
+
+/// keys through `nodes` with hermite style handles a third of the segment width
+fn build_keys(nodes: &[FitNode], monotonic: bool) -> Vec<AnimationKey> {
+    let mut keys: Vec<AnimationKey> = nodes
+        .iter()
+        .map(|node| {
+            let mut key = AnimationKey::new(Vec2::new(node.x, node.y), 0.0);
+            // kinks in the function need the in and out tangents to move independently
+            let scale = node.slope_in.abs().max(node.slope_out.abs()).max(1.0);
+            key.tangent_locked = (node.slope_in - node.slope_out).abs() <= 1e-3 * scale;
+            key
+        })
+        .collect();
+
+    for (i, pair) in nodes.windows(2).enumerate() {
+        let handle = (pair[1].x - pair[0].x) / 3.0;
+        keys[i].tangent_out = Vec2::new(handle, pair[0].slope_out * handle);
+        keys[i + 1].tangent_in = Vec2::new(-handle, -pair[1].slope_in * handle);
+    }
+
+    // the outer tangents are never used so mirror their partners in case keys get added outside later
+    if keys.len() > 1 {
+        let end = keys.len() - 1;
+        keys[0].tangent_in = -keys[0].tangent_out;
+        keys[end].tangent_out = -keys[end].tangent_in;
+    }
+
+    if monotonic {
+        splines::make_monotonic(&mut keys);
+    }
+    keys
+}
+
+/// the x of the sample between the two keys of `pair` with the largest error, if it is above `tolerance`
+fn worst_sample(pair: &[AnimationKey], samples: &[f64], targets: &[f64], tolerance: f64) -> Option<f64> {
+    let (x0, x1) = (pair[0].pos.x, pair[1].pos.x);
+    let start = samples.partition_point(|&x| x <= x0);
+    let end = samples.partition_point(|&x| x < x1);
+
+    let mut worst = (tolerance, None);
+    for i in start..end.max(start) {
+        let t = (samples[i] - x0) / (x1 - x0);
+        let error = (splines::evaluate_pair_bezier(&pair[0], &pair[1], t).y - targets[i]).abs();
+        if error > worst.0 {
+            worst = (error, Some(samples[i]));
+        }
+    }
+    worst.1
+}
@@ -0,0 +1,273 @@
+use crate::fitting;
+use crate::splines::{self, AnimationKey, Vec2};
+
+/// how close to rest a spring has to stay to count as settled, as a fraction of the distance it travels
+const SETTLE_THRESHOLD: f64 = 1e-4;
+/// steps per radian of the spring's natural frequency it is checked at when finding where it settles
+const SETTLE_STEPS_PER_RADIAN: f64 = 32.0;
+/// most steps taken looking for where a spring settles, which barely damped springs take a long time to do
+const MAX_SETTLE_STEPS: usize = 200_000;
+/// damping ratios this close to 1 are solved as critically damped, since the other solutions divide by the distance
+const CRITICAL_BAND: f64 = 1e-6;
+/// lowest damping ratio springs are solved with. Below this they ring for so long that fitting them needs more keys
+/// than it will place.
+const MIN_DAMPING_RATIO: f64 = 0.05;
+/// evenly spaced samples springs are fitted to and checked at
+const GENERATOR_SAMPLES: usize = 4096;
+
+/// The physical motion a [`Generator`] keys, travelling from 0 to 1
+#[derive(PartialEq, Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+pub enum Motion {
+    /// a damped spring released at 0 that comes to rest at 1. `velocity` is in distances per second.
+    Spring {
+        stiffness: f64,
+        damping: f64,
+        mass: f64,
+        velocity: f64,
+    },
+    /// a ball dropped from 0 onto the floor at 1 that bounces `bounces` times, each leaving at `restitution` times
+    /// the speed it arrived with
+    Bounce { restitution: f64, bounces: u32 },
+}
+
+impl Motion {
+    /// a spring that overshoots a little and settles in about a second
+    pub const SPRING: Motion = Motion::Spring {
+        stiffness: 200.0,
+        damping: 20.0,
+        mass: 1.0,
+        velocity: 0.0,
+    };
+    pub const BOUNCE: Motion = Motion::Bounce {
+        restitution: 0.5,
+        bounces: 3,
+    };
+    pub const ALL: [Motion; 2] = [Motion::SPRING, Motion::BOUNCE];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Motion::Spring { .. } => "Spring",
+            Motion::Bounce { .. } => "Bounce",
+        }
+    }
+
+    /// Seconds until a spring stays within `SETTLE_THRESHOLD` of rest, or the length of a bounce in units of the
+    /// time the first fall takes
+    pub fn duration(&self) -> f64 {
+        match *self {
+            Motion::Spring {
+                stiffness,
+                damping,
+                mass,
+                velocity,
+            } => Spring::new(stiffness, damping, mass, velocity).settle_time(),
+            Motion::Bounce { restitution, bounces } => {
+                1.0 + (1..=bounce_count(restitution, bounces))
+                    .map(|i| 2.0 * restitution.powi(i as i32))
+                    .sum::<f64>()
+            }
+        }
+    }
+
+    /// keys from (0, 0) to (1, 1) with time squeezed into 0 - 1. Springs are fitted to within `tolerance`, bounces
+    /// are parabolas between the impacts so their keys are exact.
+    fn unit_keys(&self, tolerance: f64) -> Vec<AnimationKey> {
+        match *self {
+            Motion::Spring {
+                stiffness,
+                damping,
+                mass,
+                velocity,
+            } => {
+                let spring = Spring::new(stiffness, damping, mass, velocity);
+                let duration = spring.settle_time();
+                let mut keys =
+                    fitting::fit_function(|t| spring.position(t * duration), &unit_samples(), tolerance, false);
+                // the spring is within `SETTLE_THRESHOLD` of rest by now
+                if let Some(last) = keys.last_mut() {
+                    last.pos.y = 1.0;
+                }
+                keys
+            }
+            Motion::Bounce { restitution, bounces } => {
+                let duration = self.duration();
+                // the fall is y = t², so it lands at t = 1 going at 2 and every bounce is a parabola with the same
+                // gravity that leaves and lands at the same speed
+                let mut points = vec![[0.0, 0.0, 0.0, 0.0]];
+                let mut time = 1.0;
+                let mut speed = 2.0;
+                for _ in 0..bounce_count(restitution, bounces) {
+                    let leaving = speed * restitution;
+                    points.push([time / duration, 1.0, speed * duration, -leaving * duration]);
+                    time += leaving;
+                    speed = leaving;
+                }
+                points.push([1.0, 1.0, speed * duration, speed * duration]);
+                fitting::hermite_keys(&points)
+            }
+        }
+    }
+
+    /// the motion as a function of t, 0 - 1 across its duration
+    fn function(&self) -> Box<dyn Fn(f64) -> f64> {
+        match *self {
+            Motion::Spring {
+                stiffness,
+                damping,
+                mass,
+                velocity,
+            } => {
+                let spring = Spring::new(stiffness, damping, mass, velocity);
+                let duration = spring.settle_time();
+                Box::new(move |t| spring.position(t * duration))
+            }
+            // the keys are exact
+            Motion::Bounce { .. } => {
+                let keys = self.unit_keys(0.0);
+                Box::new(move |t| splines::evaluate(&keys, t))
+            }
+        }
+    }
+}
+
+/// `GENERATOR_SAMPLES` evenly spaced across 0 - 1
+fn unit_samples() -> Vec<f64> {
+    (0..=GENERATOR_SAMPLES)
+        .map(|i| i as f64 / GENERATOR_SAMPLES as f64)
+        .collect()
+}
+
+/// the least damping a spring of `stiffness` and `mass` is solved with
+pub fn min_damping(stiffness: f64, mass: f64) -> f64 {
+    MIN_DAMPING_RATIO * 2.0 * (stiffness * mass).sqrt()
+}
+
+/// bounces that take any time at all
+fn bounce_count(restitution: f64, bounces: u32) -> u32 {
+    if restitution > 0.0 {
+        bounces
+    } else {
+        0
+    }
+}
+
+/// A curve keyed from a physical motion that stays editable through its parameters. The motion is stretched from
+/// `start` to `end`, so its time runs along x and the distance it travels along y.
+#[derive(PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Generator {
+    pub motion: Motion,
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Generator {
+    pub fn new(motion: Motion, start: Vec2, end: Vec2) -> Self {
+        Self { motion, start, end }
+    }
+
+    /// keys within `tolerance` y units of the motion
+    pub fn keys(&self, tolerance: f64) -> Vec<AnimationKey> {
+        let scale = self.end - self.start;
+        let distance = scale.y.abs();
+        let tolerance = if distance > 0.0 {
+            tolerance / distance
+        } else {
+            tolerance
+        };
+        self.motion
+            .unit_keys(tolerance)
+            .into_iter()
+            .map(|key| AnimationKey {
+                pos: key.pos * scale + self.start,
+                tangent_in: key.tangent_in * scale,
+                tangent_out: key.tangent_out * scale,
+                ..key
+            })
+            .collect()
+    }
+
+    /// the largest difference between `keys` and the motion at the samples springs are fitted to
+    pub fn max_error(&self, keys: &[AnimationKey]) -> f64 {
+        let motion = self.motion.function();
+        let scale = self.end - self.start;
+        unit_samples()
+            .into_iter()
+            .map(|t| {
+                let x = self.start.x + scale.x * t;
+                (splines::evaluate(keys, x) - (self.start.y + scale.y * motion(t))).abs()
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+/// A mass on a damped spring, solved exactly. Positions are measured from where it is released towards where it
+/// comes to rest, a distance of 1 away.
+struct Spring {
+    /// natural frequency in radians per second
+    omega: f64,
+    /// damping ratio: below 1 it overshoots, at 1 it is critically damped
+    zeta: f64,
+    velocity: f64,
+}
+
+impl Spring {
+    fn new(stiffness: f64, damping: f64, mass: f64, velocity: f64) -> Self {
+        Self {
+            omega: (stiffness / mass).sqrt(),
+            zeta: damping.max(min_damping(stiffness, mass)) / (2.0 * (stiffness * mass).sqrt()),
+            velocity,
+        }
+    }
+
+    /// distance from rest at `time` seconds and a bound on it that only shrinks from then on
+    fn displacement(&self, time: f64) -> (f64, f64) {
+        let (omega, zeta) = (self.omega, self.zeta);
+        let x0 = -1.0;
+        if zeta < 1.0 - CRITICAL_BAND {
+            let omega_d = omega * (1.0 - zeta * zeta).sqrt();
+            let a = x0;
+            let b = (self.velocity + zeta * omega * x0) / omega_d;
+            let decay = (-zeta * omega * time).exp();
+            let angle = omega_d * time;
+            (
+                decay * (a * angle.cos() + b * angle.sin()),
+                decay * (a * a + b * b).sqrt(),
+            )
+        } else if zeta <= 1.0 + CRITICAL_BAND {
+            let b = self.velocity + omega * x0;
+            let decay = (-omega * time).exp();
+            (decay * (x0 + b * time), decay * (x0.abs() + b.abs() * time))
+        } else {
+            let root = (zeta * zeta - 1.0).sqrt();
+            let (r1, r2) = (-omega * (zeta - root), -omega * (zeta + root));
+            let c2 = (self.velocity - r1 * x0) / (r2 - r1);
+            let c1 = x0 - c2;
+            (
+                c1 * (r1 * time).exp() + c2 * (r2 * time).exp(),
+                (c1.abs() + c2.abs()) * (r1 * time).exp(),
+            )
+        }
+    }
+
+    /// where the spring is at `time` seconds, 0 at release and 1 at rest
+    fn position(&self, time: f64) -> f64 {
+        1.0 + self.displacement(time).0
+    }
+
+    /// seconds until the spring stays within `SETTLE_THRESHOLD` of rest
+    fn settle_time(&self) -> f64 {
+        let step = 1.0 / (self.omega * SETTLE_STEPS_PER_RADIAN);
+        let mut settled = 0.0;
+        for i in 1..=MAX_SETTLE_STEPS {
+            let time = i as f64 * step;
+            let (displacement, bound) = self.displacement(time);
+            if displacement.abs() > SETTLE_THRESHOLD {
+                settled = time + step;
+            }
+            if bound <= SETTLE_THRESHOLD {
+                break;
+            }
+        }
+        settled
+    }
+}
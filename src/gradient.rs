@@ -0,0 +1,327 @@
+use std::fmt::Write;
+
+use egui::ecolor::{gamma_from_linear, linear_from_gamma};
+use egui::Color32;
+
+use crate::csv::Delimiter;
+use crate::splines::{self, AnimationKey};
+
+/// Colour spaces stops can be blended in
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum BlendSpace {
+    /// straight between the stored values, which is what most image editors do and looks muddy between hues
+    Srgb,
+    /// physically even light, which looks too bright towards the darker stop
+    Linear,
+    /// perceptually even steps in lightness and hue
+    Oklab,
+}
+
+impl BlendSpace {
+    pub const ALL: [BlendSpace; 3] = [BlendSpace::Srgb, BlendSpace::Linear, BlendSpace::Oklab];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BlendSpace::Srgb => "sRGB",
+            BlendSpace::Linear => "Linear",
+            BlendSpace::Oklab => "Oklab",
+        }
+    }
+
+    /// `color` as three components in this space followed by alpha
+    pub fn components(self, color: Color32) -> [f32; 4] {
+        let srgb = [color.r(), color.g(), color.b()].map(|c| c as f32 / 255.0);
+        let alpha = color.a() as f32 / 255.0;
+        let [x, y, z] = match self {
+            BlendSpace::Srgb => srgb,
+            BlendSpace::Linear => srgb.map(linear_from_gamma),
+            BlendSpace::Oklab => oklab_from_linear(srgb.map(linear_from_gamma)),
+        };
+        [x, y, z, alpha]
+    }
+
+    /// the linear RGB and alpha of a colour in this space
+    pub fn to_linear(self, [x, y, z, alpha]: [f32; 4]) -> [f32; 4] {
+        let [r, g, b] = match self {
+            BlendSpace::Srgb => [x, y, z].map(linear_from_gamma),
+            BlendSpace::Linear => [x, y, z],
+            BlendSpace::Oklab => linear_from_oklab([x, y, z]),
+        };
+        [r, g, b, alpha]
+    }
+
+    /// the nearest sRGB colour to a colour in this space
+    pub fn color(self, value: [f32; 4]) -> Color32 {
+        let [r, g, b, a] = self.to_linear(value);
+        let srgb = |c: f32| (gamma_from_linear(c.clamp(0.0, 1.0)) * 255.0).round() as u8;
+        Color32::from_rgba_unmultiplied(srgb(r), srgb(g), srgb(b), (a.clamp(0.0, 1.0) * 255.0).round() as u8)
+    }
+}
+
+/// linear sRGB to Oklab, from Björn Ottosson's reference implementation
+#[allow(clippy::excessive_precision)]
+fn oklab_from_linear([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let l = (0.4122214708 * r + 0.5363137080 * g + 0.0514459212 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+#[allow(clippy::excessive_precision)]
+fn linear_from_oklab([lightness, a, b]: [f32; 3]) -> [f32; 3] {
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076642670 * s,
+    ]
+}
+
+/// A colour at a position along a gradient
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct ColorStop {
+    /// position along the gradient, 0 - 1
+    pub t: f64,
+    pub color: Color32,
+}
+
+/// Colour stops blended in a colour space, optionally eased by a curve between each pair of stops
+#[derive(PartialEq, Clone, Debug)]
+pub struct Gradient {
+    /// sorted by `t`
+    pub stops: Vec<ColorStop>,
+    pub space: BlendSpace,
+    /// blend with `ease` instead of linearly
+    pub eased: bool,
+    /// maps how far along a pair of stops a position is, 0 - 1, to how much of the second stop is blended in
+    pub ease: Vec<AnimationKey>,
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self {
+            stops: vec![
+                ColorStop {
+                    t: 0.0,
+                    color: Color32::from_rgb(20, 30, 120),
+                },
+                ColorStop {
+                    t: 0.5,
+                    color: Color32::from_rgb(230, 60, 90),
+                },
+                ColorStop {
+                    t: 1.0,
+                    color: Color32::from_rgb(255, 230, 120),
+                },
+            ],
+            space: BlendSpace::Oklab,
+            eased: false,
+            ease: linear_ease(),
+        }
+    }
+}
+
+/// an ease that changes nothing, a straight line from (0, 0) to (1, 1)
+pub fn linear_ease() -> Vec<AnimationKey> {
+    let third = 1.0 / 3.0;
+    let handle = curve::vec2(third, third);
+    let mut keys = vec![
+        AnimationKey::new(curve::vec2(0.0, 0.0), third),
+        AnimationKey::new(curve::vec2(1.0, 1.0), third),
+    ];
+    keys[0].tangent_out = handle;
+    keys[1].tangent_in = -handle;
+    keys
+}
+
+impl Gradient {
+    pub fn sort_stops(&mut self) {
+        self.stops.sort_by(|a, b| a.t.total_cmp(&b.t));
+    }
+
+    /// how much of the second stop of a pair is blended in `f` of the way between them
+    pub fn blend_factor(&self, f: f64) -> f64 {
+        if self.eased && !self.ease.is_empty() {
+            splines::evaluate(&self.ease, f)
+        } else {
+            f
+        }
+    }
+
+    /// the colour at `t` in the blend space. Positions before the first stop or after the last take its colour.
+    pub fn sample_space(&self, t: f64) -> [f32; 4] {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return [0.0; 4];
+        };
+        if t <= first.t {
+            return self.space.components(first.color);
+        }
+        if t >= last.t {
+            return self.space.components(last.color);
+        }
+
+        let index = self.stops[1..].partition_point(|stop| stop.t < t);
+        let (a, b) = (&self.stops[index], &self.stops[index + 1]);
+        let f = self.blend_factor((t - a.t) / (b.t - a.t)) as f32;
+        let (a, b) = (self.space.components(a.color), self.space.components(b.color));
+        std::array::from_fn(|i| a[i] + (b[i] - a[i]) * f)
+    }
+
+    /// the colour at `t` as sRGB
+    pub fn sample(&self, t: f64) -> Color32 {
+        self.space.color(self.sample_space(t))
+    }
+
+    /// the colour at `t` as linear RGB and alpha
+    pub fn sample_linear(&self, t: f64) -> [f32; 4] {
+        self.space.to_linear(self.sample_space(t))
+    }
+}
+
+/// What a lookup table stores
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum LutValues {
+    /// 0 - 255 sRGB, like an 8 bit texture
+    Srgb8,
+    /// 0 - 1 linear RGB, like a float texture
+    LinearFloat,
+}
+
+impl LutValues {
+    pub const ALL: [LutValues; 2] = [LutValues::Srgb8, LutValues::LinearFloat];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LutValues::Srgb8 => "sRGB 8 bit",
+            LutValues::LinearFloat => "Linear float",
+        }
+    }
+}
+
+/// `size` evenly spaced samples from 0 to 1 as rows of t, r, g, b and a. Alpha is never premultiplied.
+pub fn write_lut(gradient: &Gradient, size: usize, values: LutValues, delimiter: Delimiter, header: bool) -> String {
+    let d = delimiter.as_char();
+    let mut out = String::new();
+    if header {
+        let _ = writeln!(out, "t{d}r{d}g{d}b{d}a");
+    }
+    let size = size.max(2);
+    for i in 0..size {
+        let t = i as f64 / (size - 1) as f64;
+        let _ = match values {
+            LutValues::Srgb8 => {
+                let [r, g, b, a] = gradient.sample(t).to_srgba_unmultiplied();
+                writeln!(out, "{t}{d}{r}{d}{g}{d}{b}{d}{a}")
+            }
+            LutValues::LinearFloat => {
+                let [r, g, b, a] = gradient.sample_linear(t);
+                writeln!(out, "{t}{d}{r}{d}{g}{d}{b}{d}{a}")
+            }
+        };
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: Color32 = Color32::from_rgb(200, 30, 40);
+    const END: Color32 = Color32::from_rgb(20, 60, 220);
+
+    fn two_stops(space: BlendSpace) -> Gradient {
+        Gradient {
+            stops: vec![ColorStop { t: 0.0, color: START }, ColorStop { t: 1.0, color: END }],
+            space,
+            ..Default::default()
+        }
+    }
+
+    fn assert_close(a: [f32; 4], b: [f32; 4]) {
+        for (a, b) in a.into_iter().zip(b) {
+            assert!((a - b).abs() < 1e-4, "{a} != {b}");
+        }
+    }
+
+    #[test]
+    fn two_stops_blend_in_each_space() {
+        for space in BlendSpace::ALL {
+            let gradient = two_stops(space);
+            assert_eq!(gradient.sample(0.0), START, "{}", space.name());
+            assert_eq!(gradient.sample(1.0), END, "{}", space.name());
+            assert_eq!(gradient.sample(-1.0), START, "{}", space.name());
+            assert_eq!(gradient.sample(2.0), END, "{}", space.name());
+
+            let (a, b) = (space.components(START), space.components(END));
+            assert_close(gradient.sample_space(0.0), a);
+            assert_close(gradient.sample_space(1.0), b);
+            assert_close(gradient.sample_space(0.5), std::array::from_fn(|i| (a[i] + b[i]) / 2.0));
+            assert_close(
+                gradient.sample_space(0.25),
+                std::array::from_fn(|i| a[i] + (b[i] - a[i]) / 4.0),
+            );
+        }
+
+        assert_eq!(two_stops(BlendSpace::Srgb).sample(0.5), Color32::from_rgb(110, 45, 130));
+        let (a, b) = (BlendSpace::Linear.components(START), BlendSpace::Linear.components(END));
+        assert_close(
+            two_stops(BlendSpace::Linear).sample_linear(0.5),
+            std::array::from_fn(|i| (a[i] + b[i]) / 2.0),
+        );
+    }
+
+    #[test]
+    fn two_stop_luts_in_each_space() {
+        for space in BlendSpace::ALL {
+            let gradient = two_stops(space);
+
+            let lut = write_lut(&gradient, 3, LutValues::Srgb8, Delimiter::Comma, true);
+            let rows: Vec<&str> = lut.lines().collect();
+            let [r, g, b, a] = gradient.sample(0.5).to_srgba_unmultiplied();
+            assert_eq!(
+                rows,
+                [
+                    "t,r,g,b,a",
+                    "0,200,30,40,255",
+                    &format!("0.5,{r},{g},{b},{a}"),
+                    "1,20,60,220,255"
+                ],
+                "{}",
+                space.name()
+            );
+
+            let lut = write_lut(&gradient, 3, LutValues::LinearFloat, Delimiter::Comma, false);
+            let rows: Vec<Vec<f32>> = lut
+                .lines()
+                .map(|row| row.split(',').map(|value| value.parse().unwrap()).collect())
+                .collect();
+            assert_eq!(rows.len(), 3);
+            for (row, t) in rows.iter().zip([0.0, 0.5, 1.0]) {
+                assert_eq!(row[0], t as f32);
+                assert_close([row[1], row[2], row[3], row[4]], gradient.sample_linear(t));
+            }
+            assert_close(
+                [rows[0][1], rows[0][2], rows[0][3], rows[0][4]],
+                BlendSpace::Linear.components(START),
+            );
+            assert_close(
+                [rows[2][1], rows[2][2], rows[2][3], rows[2][4]],
+                BlendSpace::Linear.components(END),
+            );
+        }
+    }
+
+    #[test]
+    fn sort_stops_orders_by_t() {
+        let mut gradient = Gradient::default();
+        gradient.stops.reverse();
+        gradient.sort_stops();
+        assert_eq!(gradient.stops, Gradient::default().stops);
+    }
+}
@@ -0,0 +1,430 @@
+use egui::*;
+use egui_notify::Toasts;
+
+use crate::codegen::{self, Language};
+use crate::csv::Delimiter;
+use crate::fitting;
+use crate::gradient::{BlendSpace, ColorStop, Gradient, LutValues};
+use crate::splines::{self, AnimationKey};
+
+const BAR_HEIGHT: f32 = 32.0;
+const STOP_MARKER_SIZE: f32 = 7.0;
+const EASE_SIZE: f32 = 160.0;
+const EASE_KEY_RADIUS: f32 = 4.0;
+/// columns the preview bar is drawn with
+const BAR_COLUMNS: usize = 256;
+/// the ease curve is exported as a polynomial of this degree
+const EASE_POLYNOMIAL_DEGREE: usize = 7;
+const STOP_STROKE_COLOR: Color32 = Color32::WHITE;
+const SELECTED_STOP_STROKE_COLOR: Color32 = Color32::LIGHT_RED;
+const EASE_CURVE_COLOR: Color32 = Color32::LIGHT_BLUE;
+const EASE_KEY_COLOR: Color32 = Color32::LIGHT_GREEN;
+
+#[derive(PartialEq)]
+pub struct GradientEditor {
+    gradient: Gradient,
+    /// stop shown as selected on the bar, which is the last one dragged or added
+    selected: Option<usize>,
+    /// name of the exported shader function
+    name: String,
+    code_language: Language,
+    lut_size: usize,
+    lut_values: LutValues,
+    lut_delimiter: Delimiter,
+    lut_header: bool,
+}
+
+impl Default for GradientEditor {
+    fn default() -> Self {
+        Self {
+            gradient: Gradient::default(),
+            selected: None,
+            name: "gradient".to_owned(),
+            code_language: Language::Glsl,
+            lut_size: 256,
+            lut_values: LutValues::Srgb8,
+            lut_delimiter: Delimiter::Comma,
+            lut_header: true,
+        }
+    }
+}
+
+impl super::Demo for GradientEditor {
+    fn name(&self) -> &'static str {
+        "🌈 Gradient"
+    }
+
+    fn show(&mut self, ctx: &Context, open: &mut bool, toasts: &mut Toasts) {
+        Window::new(self.name())
+            .open(open)
+            .default_size(vec2(400.0, 400.0))
+            .min_width(300.)
+            .show(ctx, |ui| self.ui(ui, toasts));
+    }
+}
+
+impl GradientEditor {
+    /// adds a stop in the middle of the widest gap, taking the colour already there
+    fn add_stop(&mut self) {
+        let stops = &self.gradient.stops;
+        let t = match stops.len() {
+            0 => 0.0,
+            1 => (stops[0].t + 0.5).fract(),
+            _ => {
+                let widest = stops
+                    .windows(2)
+                    .max_by(|a, b| (a[1].t - a[0].t).total_cmp(&(b[1].t - b[0].t)))
+                    .unwrap();
+                (widest[0].t + widest[1].t) / 2.0
+            }
+        };
+        self.insert_stop(t);
+    }
+
+    fn insert_stop(&mut self, t: f64) {
+        let color = self.gradient.sample(t);
+        let index = self.gradient.stops.partition_point(|stop| stop.t <= t);
+        self.gradient.stops.insert(index, ColorStop { t, color });
+        self.selected = Some(index);
+    }
+
+    fn ease_polynomial(&self) -> fitting::Polynomial {
+        fitting::fit_polynomial(&self.gradient.ease, 0.0..=1.0, EASE_POLYNOMIAL_DEGREE)
+    }
+
+    fn shader_code(&self) -> String {
+        let ease = self.gradient.eased.then(|| self.ease_polynomial());
+        codegen::gradient(
+            &self.gradient,
+            ease.as_ref(),
+            &codegen::identifier(&self.name),
+            self.code_language,
+        )
+    }
+
+    fn lut(&self) -> String {
+        crate::gradient::write_lut(
+            &self.gradient,
+            self.lut_size,
+            self.lut_values,
+            self.lut_delimiter,
+            self.lut_header,
+        )
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_lut(&self, toasts: &mut Toasts) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("gradient.csv")
+            .save_file()
+        {
+            match std::fs::write(&path, self.lut()) {
+                Ok(()) => {
+                    toasts.info(format!("saved {}", path.display()));
+                }
+                Err(err) => {
+                    toasts.error(format!("cannot write {}: {err}", path.display()));
+                }
+            }
+        }
+    }
+
+    /// the gradient with a draggable marker under it for every stop. Double clicking the bar adds a stop.
+    fn bar_ui(&mut self, ui: &mut Ui) {
+        let width = ui.available_width();
+        let (bar, response) = ui.allocate_exact_size(vec2(width, BAR_HEIGHT), Sense::click());
+        let mut mesh = Mesh::default();
+        for i in 0..=BAR_COLUMNS {
+            let f = i as f32 / BAR_COLUMNS as f32;
+            let color = self.gradient.sample(f as f64);
+            let x = lerp(bar.x_range(), f);
+            mesh.colored_vertex(pos2(x, bar.top()), color);
+            mesh.colored_vertex(pos2(x, bar.bottom()), color);
+            if i > 0 {
+                let v = 2 * i as u32;
+                mesh.add_triangle(v - 2, v - 1, v);
+                mesh.add_triangle(v - 1, v, v + 1);
+            }
+        }
+        ui.painter().add(Shape::mesh(mesh));
+        ui.painter()
+            .rect_stroke(bar, 0.0, ui.visuals().widgets.noninteractive.bg_stroke);
+
+        if response.double_clicked() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                self.insert_stop(remap_clamp(pos.x, bar.x_range(), 0.0..=1.0) as f64);
+            }
+        }
+
+        let (markers, _) = ui.allocate_exact_size(vec2(width, STOP_MARKER_SIZE * 2.0), Sense::hover());
+        for i in 0..self.gradient.stops.len() {
+            let stop = self.gradient.stops[i];
+            let x = lerp(bar.x_range(), stop.t as f32);
+            let tip = pos2(x, markers.top());
+            let rect = Rect::from_min_max(
+                pos2(x - STOP_MARKER_SIZE, markers.top()),
+                pos2(x + STOP_MARKER_SIZE, markers.bottom()),
+            );
+            let response = ui
+                .interact(rect, ui.id().with(("gradient_stop", i)), Sense::drag())
+                .on_hover_cursor(CursorIcon::ResizeHorizontal);
+            if response.dragged() {
+                // stops stay between their neighbours while dragged so their indices don't change
+                let min = i.checked_sub(1).map_or(0.0, |j| self.gradient.stops[j].t);
+                let max = self.gradient.stops.get(i + 1).map_or(1.0, |next| next.t);
+                let t = stop.t + (response.drag_delta().x / bar.width()) as f64;
+                self.gradient.stops[i].t = t.clamp(min, max);
+                self.selected = Some(i);
+            }
+
+            let stroke_color = if self.selected == Some(i) {
+                SELECTED_STOP_STROKE_COLOR
+            } else {
+                STOP_STROKE_COLOR
+            };
+            ui.painter().add(Shape::convex_polygon(
+                vec![
+                    tip,
+                    pos2(x + STOP_MARKER_SIZE, markers.bottom()),
+                    pos2(x - STOP_MARKER_SIZE, markers.bottom()),
+                ],
+                stop.color,
+                Stroke::new(1.5, stroke_color),
+            ));
+        }
+    }
+
+    /// the ease curve on a unit square. Keys can be dragged, double clicking adds one and right clicking removes one.
+    /// The first and last keys stay at x 0 and 1.
+    fn ease_ui(&mut self, ui: &mut Ui) {
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(EASE_SIZE), Sense::click());
+        let to_screen = emath::RectTransform::from_to(Rect::from_min_max(pos2(0.0, 1.0), pos2(1.0, 0.0)), rect);
+        let painter = ui.painter_at(rect.expand(EASE_KEY_RADIUS));
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        painter.line_segment(
+            [to_screen * pos2(0.0, 0.0), to_screen * pos2(1.0, 1.0)],
+            ui.visuals().widgets.noninteractive.bg_stroke,
+        );
+
+        let keys = &mut self.gradient.ease;
+        let mut changed = false;
+        let mut remove = None;
+        let last = keys.len() - 1;
+        for i in 0..keys.len() {
+            let pos = to_screen * pos2(keys[i].pos.x as f32, keys[i].pos.y as f32);
+            let key_rect = Rect::from_center_size(pos, Vec2::splat(EASE_KEY_RADIUS * 3.0));
+            let response = ui.interact(key_rect, ui.id().with(("ease_key", i)), Sense::click_and_drag());
+            if response.dragged() {
+                let delta = response.drag_delta() / rect.size();
+                if i > 0 && i < last {
+                    let (min, max) = (keys[i - 1].pos.x, keys[i + 1].pos.x);
+                    keys[i].pos.x = (keys[i].pos.x + delta.x as f64).clamp(min, max);
+                }
+                keys[i].pos.y = (keys[i].pos.y - delta.y as f64).clamp(0.0, 1.0);
+                changed = true;
+            }
+            if response.secondary_clicked() && i > 0 && i < last {
+                remove = Some(i);
+            }
+            painter.circle_filled(pos, EASE_KEY_RADIUS, EASE_KEY_COLOR);
+        }
+        if let Some(index) = remove {
+            keys.remove(index);
+            changed = true;
+        }
+        if response.double_clicked() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                let pos = to_screen.inverse() * pos;
+                let x = (pos.x as f64).clamp(0.0, 1.0);
+                let index = keys.partition_point(|key| key.pos.x <= x).clamp(1, last);
+                keys.insert(
+                    index,
+                    AnimationKey::new(curve::vec2(x, (pos.y as f64).clamp(0.0, 1.0)), 0.0),
+                );
+                changed = true;
+            }
+        }
+        if changed {
+            auto_tangents(keys);
+        }
+
+        let steps = 64;
+        let points: Vec<Pos2> = (0..=steps)
+            .map(|i| {
+                let x = i as f64 / steps as f64;
+                to_screen * pos2(x as f32, splines::evaluate(keys, x) as f32)
+            })
+            .collect();
+        painter.add(Shape::line(points, Stroke::new(2.0, EASE_CURVE_COLOR)));
+    }
+
+    fn set_ease(&mut self, start_slope: f64, end_slope: f64) {
+        let third = 1.0 / 3.0;
+        let mut start = AnimationKey::new(curve::vec2(0.0, 0.0), third);
+        start.tangent_out.y = start_slope * third;
+        let mut end = AnimationKey::new(curve::vec2(1.0, 1.0), third);
+        end.tangent_in.y = -end_slope * third;
+        self.gradient.ease = vec![start, end];
+    }
+
+    fn ui(&mut self, ui: &mut Ui, toasts: &mut Toasts) {
+        self.bar_ui(ui);
+
+        ui.horizontal(|ui| {
+            ComboBox::from_id_source("gradient_space")
+                .selected_text(self.gradient.space.name())
+                .show_ui(ui, |ui| {
+                    for space in BlendSpace::ALL {
+                        ui.selectable_value(&mut self.gradient.space, space, space.name());
+                    }
+                })
+                .response
+                .on_hover_text("Colour space the stops are blended in");
+            if ui.button("Add Stop").clicked() {
+                self.add_stop();
+            }
+            if ui.button("Reverse").clicked() {
+                self.gradient.stops.reverse();
+                for stop in &mut self.gradient.stops {
+                    stop.t = 1.0 - stop.t;
+                }
+                self.selected = None;
+            }
+        });
+
+        CollapsingHeader::new("Stops").default_open(true).show(ui, |ui| {
+            let mut remove = None;
+            let mut moved = false;
+            let removable = self.gradient.stops.len() > 1;
+            Grid::new("gradient_stops").num_columns(3).show(ui, |ui| {
+                for (i, stop) in self.gradient.stops.iter_mut().enumerate() {
+                    moved |= ui
+                        .add(DragValue::new(&mut stop.t).speed(0.005).clamp_range(0.0..=1.0))
+                        .changed();
+                    ui.color_edit_button_srgba(&mut stop.color);
+                    if ui
+                        .add_enabled(removable, Button::new("🗑"))
+                        .on_hover_text("Remove the stop")
+                        .clicked()
+                    {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+            if moved {
+                self.gradient.sort_stops();
+                self.selected = None;
+            }
+            if let Some(index) = remove {
+                self.gradient.stops.remove(index);
+                self.selected = None;
+            }
+        });
+
+        CollapsingHeader::new("Ease").show(ui, |ui| {
+            ui.checkbox(&mut self.gradient.eased, "Ease Between Stops")
+                .on_hover_text("Blend each pair of stops along the curve instead of linearly");
+            ui.add_enabled_ui(self.gradient.eased, |ui| {
+                ui.horizontal(|ui| {
+                    if ui.button("Linear").clicked() {
+                        self.set_ease(1.0, 1.0);
+                    }
+                    if ui.button("Smooth").clicked() {
+                        self.set_ease(0.0, 0.0);
+                    }
+                    if ui.button("Ease In").clicked() {
+                        self.set_ease(0.0, 2.0);
+                    }
+                    if ui.button("Ease Out").clicked() {
+                        self.set_ease(2.0, 0.0);
+                    }
+                });
+                self.ease_ui(ui);
+            });
+        });
+
+        CollapsingHeader::new("Export LUT").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(
+                    DragValue::new(&mut self.lut_size)
+                        .clamp_range(2..=4096)
+                        .prefix("Size: "),
+                );
+                ComboBox::from_id_source("gradient_lut_values")
+                    .selected_text(self.lut_values.name())
+                    .show_ui(ui, |ui| {
+                        for values in LutValues::ALL {
+                            ui.selectable_value(&mut self.lut_values, values, values.name());
+                        }
+                    });
+                ui.checkbox(&mut self.lut_header, "Header");
+                ComboBox::from_id_source("gradient_lut_delimiter")
+                    .selected_text(self.lut_delimiter.name())
+                    .show_ui(ui, |ui| {
+                        for delimiter in Delimiter::ALL {
+                            ui.selectable_value(&mut self.lut_delimiter, delimiter, delimiter.name());
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Copy LUT")
+                    .on_hover_text("Copy t, r, g, b and a rows to the clipboard")
+                    .clicked()
+                {
+                    ui.output_mut(|o| o.copied_text = self.lut());
+                    toasts.info("LUT copied to clipboard");
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Save LUT...").clicked() {
+                    self.save_lut(toasts);
+                }
+            });
+        });
+
+        CollapsingHeader::new("Shader Code").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut self.name).desired_width(100.0));
+                ComboBox::from_id_source("gradient_language")
+                    .selected_text(self.code_language.name())
+                    .show_ui(ui, |ui| {
+                        for language in Language::SHADERS {
+                            ui.selectable_value(&mut self.code_language, language, language.name());
+                        }
+                    });
+                if ui
+                    .button("Copy Code")
+                    .on_hover_text("Copy a function returning linear RGB and alpha")
+                    .clicked()
+                {
+                    ui.output_mut(|o| o.copied_text = self.shader_code());
+                    toasts.info("code copied to clipboard");
+                }
+            });
+            if self.gradient.eased {
+                let error = fitting::polynomial_error(&self.gradient.ease, &self.ease_polynomial());
+                ui.label(format!("ease polynomial max error {error:.5}"));
+            }
+            crate::syntax_highlighting::code_view_ui(ui, &self.shader_code());
+        });
+    }
+}
+
+/// Catmull-Rom slopes for the inner keys, then limited so the ease never runs backwards. The end slopes are kept.
+fn auto_tangents(keys: &mut [AnimationKey]) {
+    for i in 1..keys.len().saturating_sub(1) {
+        let (prev, next) = (keys[i - 1].pos, keys[i + 1].pos);
+        let slope = if next.x > prev.x {
+            (next.y - prev.y) / (next.x - prev.x)
+        } else {
+            0.0
+        };
+        let key = &mut keys[i];
+        key.tangent_in = curve::vec2(-1.0, -slope);
+        key.tangent_out = curve::vec2(1.0, slope);
+        key.tangent_locked = true;
+    }
+    splines::make_monotonic(keys);
+}
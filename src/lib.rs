@@ -1,8 +1,20 @@
 mod app;
 pub use app::App;
+/// the egui free curve model and evaluation the editor is built on
+pub use curve;
 use egui_notify::Toasts;
 
+mod codegen;
+mod csv;
 mod curve_editor;
+mod document;
+mod expression;
+mod fitting;
+mod generators;
+mod gradient;
+mod gradient_editor;
+mod path_editor;
+mod presets;
 #[allow(dead_code)]
 mod splines;
 #[allow(dead_code)]
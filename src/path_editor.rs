@@ -0,0 +1,438 @@
+use egui::plot::{PlotBounds, PlotPoint, PlotUi};
+use egui::*;
+use egui_notify::Toasts;
+use plot::{Line, Plot, Points};
+
+use crate::codegen;
+use crate::csv::Delimiter;
+use crate::curve_editor::{
+    draw_handles, from_plot, pick_handle, to_curve_vec, toggle_tangent, translate_key, AnimationKeyPoint,
+    AnimationKeyPointField,
+};
+use crate::splines::{self, AnimationKey, Path, Precision};
+
+const TANGENT_LENGTH: f64 = 0.15;
+const BOUNDS_OVERSHOOT: f64 = 0.2;
+const PATH_COLOR: Color32 = Color32::LIGHT_BLUE;
+const CONTROL_POINT_LINE_COLOR: Color32 = Color32::LIGHT_GREEN;
+const MARKER_COLOR: Color32 = Color32::WHITE;
+const MARKER_RADIUS: f32 = 6.0;
+
+/// How exported points are spread along the path
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum Spacing {
+    /// the same number of points on every segment, bunching up where the handles are short
+    Parameter,
+    /// the same distance between every point
+    Distance,
+}
+
+impl Spacing {
+    const ALL: [Spacing; 2] = [Spacing::Parameter, Spacing::Distance];
+
+    fn name(self) -> &'static str {
+        match self {
+            Spacing::Parameter => "Per Segment",
+            Spacing::Distance => "Even Distance",
+        }
+    }
+}
+
+/// Edits a free-form 2D path. Keys stay in the order they were added, so the path can cross itself and loop back.
+#[derive(PartialEq)]
+pub struct PathEditor {
+    path: Path,
+    curve_resolution: f32,
+    dragged_object: Option<(usize, AnimationKeyPointField)>,
+    hovered_object: Option<(usize, AnimationKeyPointField)>,
+    points_for_drawing: Vec<AnimationKeyPoint>,
+    /// name of the exported function
+    name: String,
+    code_language: codegen::Language,
+    precision: Precision,
+    spacing: Spacing,
+    /// points exported with `Spacing::Distance`, or per segment with `Spacing::Parameter`
+    export_points: usize,
+    delimiter: Delimiter,
+    header: bool,
+    /// distance along the path of the marker that previews moving at constant speed
+    marker_distance: f64,
+    playing: bool,
+    /// path units per second
+    playback_speed: f64,
+}
+
+impl Default for PathEditor {
+    fn default() -> Self {
+        let key = |x: f64, y: f64, tx: f64, ty: f64| {
+            let mut key = AnimationKey::new(curve::vec2(x, y), TANGENT_LENGTH);
+            key.tangent_out = curve::vec2(tx, ty);
+            key.tangent_in = -key.tangent_out;
+            key
+        };
+        Self {
+            path: Path::new(
+                vec![
+                    key(0.0, 0.0, 0.3, 0.0),
+                    key(1.0, 0.5, 0.0, 0.3),
+                    key(0.5, 1.0, -0.3, 0.0),
+                    key(0.2, 0.6, 0.0, -0.2),
+                ],
+                false,
+            ),
+            curve_resolution: 0.005,
+            dragged_object: None,
+            hovered_object: None,
+            points_for_drawing: vec![],
+            name: "path".to_owned(),
+            code_language: codegen::Language::Glsl,
+            precision: Precision::default(),
+            spacing: Spacing::Distance,
+            export_points: 32,
+            delimiter: Delimiter::Comma,
+            header: true,
+            marker_distance: 0.0,
+            playing: false,
+            playback_speed: 0.5,
+        }
+    }
+}
+
+impl super::Demo for PathEditor {
+    fn name(&self) -> &'static str {
+        "〰 Path"
+    }
+
+    fn show(&mut self, ctx: &Context, open: &mut bool, toasts: &mut Toasts) {
+        Window::new(self.name())
+            .open(open)
+            .default_size(vec2(400.0, 400.0))
+            .min_width(200.)
+            .min_height(300.)
+            .show(ctx, |ui| self.ui(ui, toasts));
+    }
+}
+
+impl PathEditor {
+    /// the exported points as rows of x and y
+    fn export_csv(&self) -> String {
+        let points = match self.spacing {
+            Spacing::Parameter => self.path.sample(self.export_points),
+            Spacing::Distance => splines::path_arc_length_table(&self.path, None).even_points(self.export_points),
+        };
+        let d = self.delimiter.as_char();
+        let mut out = String::new();
+        if self.header {
+            out += &format!("x{d}y\n");
+        }
+        for p in points {
+            out += &format!("{}{d}{}\n", self.precision.format(p.x), self.precision.format(p.y));
+        }
+        out
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_csv(&self, toasts: &mut Toasts) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name("path.csv")
+            .save_file()
+        {
+            match std::fs::write(&path, self.export_csv()) {
+                Ok(()) => {
+                    toasts.info(format!("saved {}", path.display()));
+                }
+                Err(err) => {
+                    toasts.error(format!("cannot write {}: {err}", path.display()));
+                }
+            }
+        }
+    }
+
+    fn code(&self) -> String {
+        codegen::path(
+            &self.path,
+            &codegen::identifier(&self.name),
+            self.code_language,
+            self.precision,
+        )
+    }
+
+    /// adds a key after the last one, pointing its handles away from it
+    fn add_key(&mut self, pos: splines::Vec2) {
+        let mut key = AnimationKey::new(pos, TANGENT_LENGTH);
+        if let Some(last) = self.path.keys.last() {
+            let direction = pos - last.pos;
+            let length = direction.length();
+            if length > 0.0 {
+                key.tangent_out = direction * (TANGENT_LENGTH / length);
+                key.tangent_in = -key.tangent_out;
+            }
+        }
+        self.path.keys.push(key);
+    }
+
+    fn plot_bounds(&self) -> PlotBounds {
+        let (mut min, mut max) = ([f64::MAX; 2], [f64::MIN; 2]);
+        for key in &self.path.keys {
+            for p in [key.pos, key.tangent_in_world(), key.tangent_out_world()] {
+                for axis in 0..2 {
+                    min[axis] = min[axis].min(p[axis]);
+                    max[axis] = max[axis].max(p[axis]);
+                }
+            }
+        }
+        if min[0] > max[0] {
+            return PlotBounds::from_min_max([0.0, 0.0], [1.0, 1.0]);
+        }
+        let overshoot = (max[0] - min[0]).max(max[1] - min[1]).max(0.1) * BOUNDS_OVERSHOOT;
+        PlotBounds::from_min_max(
+            [min[0] - overshoot, min[1] - overshoot],
+            [max[0] + overshoot, max[1] + overshoot],
+        )
+    }
+
+    fn draw_tangent_lines(&self, plot_ui: &mut PlotUi) {
+        let last = self.path.keys.len().saturating_sub(1);
+        for (i, key) in self.path.keys.iter().enumerate() {
+            let mut pts = Vec::with_capacity(3);
+            if i > 0 || self.path.closed {
+                pts.push(key.tangent_in_world().into());
+            }
+            pts.push(key.pos.into());
+            if i < last || self.path.closed {
+                pts.push(key.tangent_out_world().into());
+            }
+            plot_ui.line(Line::new(pts).color(CONTROL_POINT_LINE_COLOR));
+        }
+    }
+
+    fn ui(&mut self, ui: &mut Ui, toasts: &mut Toasts) -> Response {
+        ui.collapsing("Instructions", |ui| {
+            ui.label("Command/Ctrl click tangent to toggle tangent lock.");
+            ui.label("Alt click key to delete.");
+            ui.label("Alt click empty space to add a key after the last one.");
+        });
+        ui.separator();
+
+        let table = splines::path_arc_length_table(&self.path, None);
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.path.closed, "Closed")
+                .on_hover_text("Join the last key back to the first");
+            if ui.button("Reverse").clicked() {
+                self.path.keys.reverse();
+                for key in &mut self.path.keys {
+                    std::mem::swap(&mut key.tangent_in, &mut key.tangent_out);
+                }
+            }
+            ui.label(format!("length {:.4}", table.length()));
+        });
+
+        ui.horizontal(|ui| {
+            let label = if self.playing { "⏹ Stop" } else { "▶ Play" };
+            if ui.button(label).clicked() {
+                self.playing = !self.playing;
+                self.marker_distance = 0.0;
+            }
+            ui.add(
+                DragValue::new(&mut self.playback_speed)
+                    .speed(0.01)
+                    .clamp_range(0.0..=f64::MAX)
+                    .suffix(" units/s"),
+            )
+            .on_hover_text("The marker moves along the path at this constant speed");
+        });
+        if self.playing {
+            let dt = ui.input(|i| i.stable_dt).min(0.1) as f64;
+            self.marker_distance += self.playback_speed * dt;
+            if self.marker_distance > table.length() {
+                self.marker_distance = if self.path.closed {
+                    self.marker_distance % table.length().max(f64::EPSILON)
+                } else {
+                    0.0
+                };
+            }
+            ui.ctx().request_repaint();
+        }
+
+        ui.collapsing("Export Points", |ui| {
+            ui.horizontal(|ui| {
+                ComboBox::from_id_source("path_spacing")
+                    .selected_text(self.spacing.name())
+                    .show_ui(ui, |ui| {
+                        for spacing in Spacing::ALL {
+                            ui.selectable_value(&mut self.spacing, spacing, spacing.name());
+                        }
+                    });
+                let prefix = match self.spacing {
+                    Spacing::Parameter => "Per Segment: ",
+                    Spacing::Distance => "Points: ",
+                };
+                ui.add(
+                    DragValue::new(&mut self.export_points)
+                        .clamp_range(1..=100_000)
+                        .prefix(prefix),
+                );
+                ui.checkbox(&mut self.header, "Header");
+                ComboBox::from_id_source("path_delimiter")
+                    .selected_text(self.delimiter.name())
+                    .show_ui(ui, |ui| {
+                        for delimiter in Delimiter::ALL {
+                            ui.selectable_value(&mut self.delimiter, delimiter, delimiter.name());
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                if ui
+                    .button("Copy CSV")
+                    .on_hover_text("Copy x and y rows to the clipboard")
+                    .clicked()
+                {
+                    ui.output_mut(|o| o.copied_text = self.export_csv());
+                    toasts.info("CSV copied to clipboard");
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button("Save CSV...").clicked() {
+                    self.save_csv(toasts);
+                }
+            });
+        });
+
+        ui.collapsing("Code", |ui| {
+            ui.horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut self.name).desired_width(100.0));
+                ComboBox::from_id_source("path_language")
+                    .selected_text(self.code_language.name())
+                    .show_ui(ui, |ui| {
+                        for language in codegen::Language::ALL {
+                            ui.selectable_value(&mut self.code_language, language, language.name());
+                        }
+                    });
+                ComboBox::from_id_source("path_precision")
+                    .selected_text(self.precision.name())
+                    .show_ui(ui, |ui| {
+                        for precision in Precision::ALL {
+                            ui.selectable_value(&mut self.precision, precision, precision.name());
+                        }
+                    });
+                if ui
+                    .button("Copy Code")
+                    .on_hover_text("Copy a function returning the point t of the way along the path")
+                    .clicked()
+                {
+                    ui.output_mut(|o| o.copied_text = self.code());
+                    toasts.info("code copied to clipboard");
+                }
+            });
+            crate::syntax_highlighting::code_view_ui(ui, &self.code());
+        });
+
+        self.points_for_drawing
+            .resize_with(self.path.keys.len(), Default::default);
+
+        let plot_bounds = self.plot_bounds();
+        let plot = Plot::new("path_plot")
+            .allow_drag(false)
+            .allow_scroll(false)
+            .allow_zoom(false)
+            .allow_boxed_zoom(false)
+            .allow_double_click_reset(false)
+            .show_x(false)
+            .show_y(false)
+            .data_aspect(1.0)
+            .min_size(vec2(128., 128.))
+            .height(ui.available_height());
+
+        let InnerResponse {
+            mut response,
+            inner: (left_click_pos, drag_delta, ptr_coord, ptr_screen),
+        } = plot.show(ui, |plot_ui| {
+            if !self.path.keys.is_empty() {
+                let pts = splines::flatten_path(&self.path, Some(self.curve_resolution));
+                plot_ui.line(Line::new(pts).color(PATH_COLOR).width(2.0));
+            }
+            self.draw_tangent_lines(plot_ui);
+            if self.playing {
+                let p = table.point_at_distance(self.marker_distance);
+                plot_ui.points(Points::new(vec![[p.x, p.y]]).radius(MARKER_RADIUS).color(MARKER_COLOR));
+            }
+            plot_ui.set_plot_bounds(plot_bounds);
+
+            let left_click_pos = plot_ui.ctx().input(|i| {
+                if i.pointer.primary_clicked() {
+                    return i.pointer.interact_pos();
+                }
+                None
+            });
+
+            // convert to screen space for drawing
+            for (i, key) in self.path.keys.iter().enumerate() {
+                let screen = |p: splines::Vec2| plot_ui.screen_from_plot(PlotPoint::new(p.x, p.y)).to_vec2();
+                self.points_for_drawing[i] = AnimationKeyPoint {
+                    pos: screen(key.pos),
+                    tangent_in: screen(key.tangent_in_world()),
+                    tangent_out: screen(key.tangent_out_world()),
+                };
+            }
+
+            (
+                left_click_pos,
+                plot_ui.pointer_coordinate_drag_delta(),
+                plot_ui.pointer_coordinate(),
+                plot_ui.pointer_coordinate().map(|pt| plot_ui.screen_from_plot(pt)),
+            )
+        });
+
+        if let (Some(click_pos), Some(_)) = (left_click_pos, ptr_coord) {
+            self.dragged_object = pick_handle(&self.points_for_drawing, click_pos, self.path.closed);
+        }
+        if response.drag_released() {
+            self.dragged_object = None;
+        }
+        if let Some((index, field)) = self.dragged_object {
+            translate_key(&mut self.path.keys[index], &field, to_curve_vec(drag_delta));
+        }
+
+        self.hovered_object = None;
+        if let (None, Some(ptr_screen)) = (self.dragged_object, ptr_screen) {
+            self.hovered_object = pick_handle(&self.points_for_drawing, ptr_screen, self.path.closed);
+        }
+
+        draw_handles(
+            &ui.painter_at(response.rect),
+            &self.points_for_drawing,
+            &self.path.keys,
+            self.hovered_object,
+            self.path.closed,
+        );
+
+        if response.clicked() {
+            let (command, alt) = ui.input(|i| (i.modifiers.command, i.modifiers.alt));
+            match self.hovered_object {
+                Some((index, AnimationKeyPointField::Pos)) if alt => {
+                    if self.path.keys.len() > 2 {
+                        self.path.keys.remove(index);
+                        toasts.info("key removed");
+                    } else {
+                        toasts.error("cannot remove key");
+                    }
+                }
+                Some((index, field)) if command && field != AnimationKeyPointField::Pos => {
+                    toggle_tangent(&mut self.path.keys[index], field);
+                    toasts.info("tangent lock toggled");
+                }
+                None if alt => {
+                    if let Some(pos) = ptr_coord {
+                        self.add_key(from_plot(pos));
+                        toasts.info("key added");
+                    }
+                }
+                _ => {}
+            }
+        }
+        if self.hovered_object.is_some() {
+            response = response.on_hover_cursor(CursorIcon::Grab);
+        }
+        response
+    }
+}
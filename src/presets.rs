@@ -0,0 +1,258 @@
+use std::f64::consts::PI;
+
+use crate::fitting;
+use crate::splines::{AnimationKey, Vec2};
+
+/// max error of presets that are fitted rather than exact, in units of the 0 - 1 square
+const PRESET_TOLERANCE: f64 = 1e-4;
+/// evenly spaced samples the fitted presets are checked against
+const PRESET_SAMPLES: usize = 8192;
+/// how far back eases pull before setting off, Penner's 10% overshoot
+const BACK_OVERSHOOT: f64 = 1.70158;
+
+/// The shapes of Robert Penner's easing functions
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum EaseFamily {
+    Quad,
+    Cubic,
+    Quart,
+    Expo,
+    Sine,
+    Back,
+    Elastic,
+    Bounce,
+}
+
+impl EaseFamily {
+    pub const ALL: [EaseFamily; 8] = [
+        EaseFamily::Quad,
+        EaseFamily::Cubic,
+        EaseFamily::Quart,
+        EaseFamily::Expo,
+        EaseFamily::Sine,
+        EaseFamily::Back,
+        EaseFamily::Elastic,
+        EaseFamily::Bounce,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EaseFamily::Quad => "Quad",
+            EaseFamily::Cubic => "Cubic",
+            EaseFamily::Quart => "Quart",
+            EaseFamily::Expo => "Expo",
+            EaseFamily::Sine => "Sine",
+            EaseFamily::Back => "Back",
+            EaseFamily::Elastic => "Elastic",
+            EaseFamily::Bounce => "Bounce",
+        }
+    }
+
+    /// the ease in, from 0 at t = 0 to 1 at t = 1
+    pub fn ease_in(self, t: f64) -> f64 {
+        match self {
+            EaseFamily::Quad => t * t,
+            EaseFamily::Cubic => t * t * t,
+            EaseFamily::Quart => t * t * t * t,
+            EaseFamily::Expo if t <= 0.0 => 0.0,
+            EaseFamily::Expo => 2f64.powf(10.0 * t - 10.0),
+            EaseFamily::Sine => 1.0 - (t * PI / 2.0).cos(),
+            EaseFamily::Back => (BACK_OVERSHOOT + 1.0) * t * t * t - BACK_OVERSHOOT * t * t,
+            EaseFamily::Elastic if t <= 0.0 || t >= 1.0 => t.clamp(0.0, 1.0),
+            EaseFamily::Elastic => -(2f64.powf(10.0 * t - 10.0)) * ((10.0 * t - 10.75) * 2.0 * PI / 3.0).sin(),
+            EaseFamily::Bounce => 1.0 - bounce_out(1.0 - t),
+        }
+    }
+
+    /// the slope of the ease in for the families that are cubics, which keys reproduce exactly
+    fn ease_in_slope(self, t: f64) -> Option<f64> {
+        match self {
+            EaseFamily::Quad => Some(2.0 * t),
+            EaseFamily::Cubic => Some(3.0 * t * t),
+            EaseFamily::Back => Some(3.0 * (BACK_OVERSHOOT + 1.0) * t * t - 2.0 * BACK_OVERSHOOT * t),
+            _ => None,
+        }
+    }
+}
+
+/// Penner's bounce, four parabolas each a quarter the height of the one before
+fn bounce_out(t: f64) -> f64 {
+    const N: f64 = 7.5625;
+    const D: f64 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Which end of an ease is slow
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum EaseMode {
+    In,
+    Out,
+    /// the in ease at double speed followed by its mirror image
+    InOut,
+}
+
+impl EaseMode {
+    pub const ALL: [EaseMode; 3] = [EaseMode::In, EaseMode::Out, EaseMode::InOut];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EaseMode::In => "In",
+            EaseMode::Out => "Out",
+            EaseMode::InOut => "In Out",
+        }
+    }
+
+    /// `f` in this mode given the ease in `f`
+    fn apply(self, f: impl Fn(f64) -> f64, t: f64) -> f64 {
+        match self {
+            EaseMode::In => f(t),
+            EaseMode::Out => 1.0 - f(1.0 - t),
+            EaseMode::InOut if t < 0.5 => f(2.0 * t) / 2.0,
+            EaseMode::InOut => 1.0 - f(2.0 - 2.0 * t) / 2.0,
+        }
+    }
+
+    /// the slope in this mode given the slope `f` of the ease in
+    fn apply_slope(self, f: impl Fn(f64) -> f64, t: f64) -> f64 {
+        match self {
+            EaseMode::In => f(t),
+            EaseMode::Out => f(1.0 - t),
+            EaseMode::InOut if t < 0.5 => f(2.0 * t),
+            EaseMode::InOut => f(2.0 - 2.0 * t),
+        }
+    }
+
+    /// where the keys of an exact preset go, one key per cubic piece
+    fn key_times(self) -> &'static [f64] {
+        match self {
+            EaseMode::In | EaseMode::Out => &[0.0, 1.0],
+            EaseMode::InOut => &[0.0, 0.5, 1.0],
+        }
+    }
+}
+
+/// a Penner ease at `t`, 0 - 1
+pub fn ease(family: EaseFamily, mode: EaseMode, t: f64) -> f64 {
+    mode.apply(|t| family.ease_in(t), t)
+}
+
+/// Perlin's smoothstep with flat second derivatives at the ends as well
+fn smootherstep(t: f64) -> f64 {
+    t * t * t * (t * (6.0 * t - 15.0) + 10.0)
+}
+
+/// A named curve shape that can be inserted into a curve. Keys go from (0, 0) to (1, 1), though they can overshoot
+/// in between, and are stretched over the domain and range on insertion.
+#[derive(PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub keys: Vec<AnimationKey>,
+}
+
+impl Preset {
+    pub fn new(name: impl Into<String>, keys: Vec<AnimationKey>) -> Self {
+        Self {
+            name: name.into(),
+            keys,
+        }
+    }
+}
+
+/// Linear, step, smoothstep, smootherstep and every Penner ease. The quad, cubic and back eases and smoothstep are
+/// cubics so their keys are exact. The rest are fitted to within `PRESET_TOLERANCE`.
+pub fn builtin_presets() -> Vec<Preset> {
+    let mut presets = vec![
+        Preset::new(
+            "Linear",
+            fitting::hermite_keys(&[[0.0, 0.0, 1.0, 1.0], [1.0, 1.0, 1.0, 1.0]]),
+        ),
+        Preset::new("Step", step_keys()),
+        Preset::new(
+            "Smoothstep",
+            fitting::hermite_keys(&[[0.0, 0.0, 0.0, 0.0], [1.0, 1.0, 0.0, 0.0]]),
+        ),
+        Preset::new("Smootherstep", fit(smootherstep)),
+    ];
+    for family in EaseFamily::ALL {
+        for mode in EaseMode::ALL {
+            let keys = if family.ease_in_slope(0.0).is_some() {
+                let slope = |t| family.ease_in_slope(t).unwrap_or_default();
+                let points: Vec<[f64; 4]> = mode
+                    .key_times()
+                    .iter()
+                    .map(|&t| {
+                        let slope = mode.apply_slope(slope, t);
+                        [t, ease(family, mode, t), slope, slope]
+                    })
+                    .collect();
+                fitting::hermite_keys(&points)
+            } else {
+                fit(|t| ease(family, mode, t))
+            };
+            presets.push(Preset::new(format!("{} {}", family.name(), mode.name()), keys));
+        }
+    }
+    presets
+}
+
+/// holds at 0 then jumps to 1 halfway, with two keys at the same x
+fn step_keys() -> Vec<AnimationKey> {
+    let handle = 1.0 / 6.0;
+    let mut keys = vec![
+        AnimationKey::new(Vec2::new(0.0, 0.0), handle),
+        AnimationKey::new(Vec2::new(0.5, 0.0), handle),
+        AnimationKey::new(Vec2::new(0.5, 1.0), handle),
+        AnimationKey::new(Vec2::new(1.0, 1.0), handle),
+    ];
+    // the jump is drawn as a straight line
+    keys[1].tangent_out = Vec2::ZERO;
+    keys[1].tangent_locked = false;
+    keys[2].tangent_in = Vec2::ZERO;
+    keys[2].tangent_locked = false;
+    keys
+}
+
+fn fit(f: impl Fn(f64) -> f64) -> Vec<AnimationKey> {
+    let samples: Vec<f64> = (0..=PRESET_SAMPLES).map(|i| i as f64 / PRESET_SAMPLES as f64).collect();
+    fitting::fit_function(f, &samples, PRESET_TOLERANCE, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::splines;
+
+    #[test]
+    fn baked_presets_match_evaluate() {
+        let presets = builtin_presets();
+        let stacked = |keys: &[AnimationKey]| keys.windows(2).any(|pair| pair[0].pos.x == pair[1].pos.x);
+        assert!(presets.iter().any(|preset| stacked(&preset.keys)));
+
+        for preset in &presets {
+            let baked = curve::BakedCurve::new(&preset.keys);
+            let xs = (0..=1000)
+                .map(|i| i as f64 / 1000.0 * 1.2 - 0.1)
+                .chain(preset.keys.iter().map(|key| key.pos.x));
+            for x in xs {
+                let expected = splines::evaluate(&preset.keys, x);
+                assert!(
+                    (baked.evaluate(x) - expected).abs() < 1e-9,
+                    "{} at x = {x}: {} != {expected}",
+                    preset.name,
+                    baked.evaluate(x)
+                );
+            }
+        }
+    }
+}
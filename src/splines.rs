@@ -1,49 +1,71 @@
+use std::ops::RangeInclusive;
+
 use eframe::epaint::CubicBezierShape;
 
-use egui::{Color32, Stroke, Vec2};
+use egui::{pos2, Color32, Pos2, Stroke};
+
+// the math lives in the egui free `curve` crate. This module adds what the editor needs for drawing.
+pub use curve::{
+    apply_tcb, curve_is_monotonic, evaluate, evaluate_derivative, evaluate_inverse, evaluate_modified,
+    evaluate_pair_bezier, keys_are_monotonic, make_monotonic, segment_y_extents, ArcLengthTable, InverseError,
+    WrapMode,
+};
+
+/// Keys are authored in f64 so long timelines, like a day/night cycle in seconds, keep their precision. Exports round
+/// to f32 when asked to.
+pub type AnimationKey = curve::AnimationKey<f64>;
+pub type Vec2 = curve::Vec2<f64>;
+pub type Modifier = curve::Modifier<f64>;
+pub type ModifierKind = curve::ModifierKind<f64>;
+pub type Path = curve::Path<f64>;
+pub type Tcb = curve::Tcb<f64>;
+
+/// The float width exports are written with
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default)]
+pub enum Precision {
+    /// f32, which is what shaders and most runtimes use
+    #[default]
+    Single,
+    /// f64, keeping everything the keys were authored with
+    Double,
+}
 
-use crate::curve_editor::AnimationKey;
+impl Precision {
+    pub const ALL: [Precision; 2] = [Precision::Single, Precision::Double];
 
-pub fn get_bezier(points: &Vec<AnimationKey>, curve_resolution: usize) -> Vec<[f64; 2]> {
-    let mut pts: Vec<[f64; 2]> = Vec::new();
-
-    for i in 0..=curve_resolution {
-        let t = i as f32 / curve_resolution as f32;
-        let value = evaluate(&points, t);
-        pts.push([t as f64, value as f64]);
+    pub fn name(self) -> &'static str {
+        match self {
+            Precision::Single => "Single (f32)",
+            Precision::Double => "Double (f64)",
+        }
     }
 
-    pts
+    /// the shortest text that reads back as `value` rounded to this precision
+    pub fn format(self, value: f64) -> String {
+        match self {
+            Precision::Single => (value as f32).to_string(),
+            Precision::Double => value.to_string(),
+        }
+    }
 }
 
-pub fn evaluate(pts: &Vec<AnimationKey>, time: f32) -> f32 {
-    if time <= pts[0].pos.x {
-        return pts[0].pos.y;
-    }
-    if time >= pts[pts.len() - 1].pos.x {
-        return pts[pts.len() - 1].pos.y;
-    }
+/// samples the curve at `curve_resolution + 1` evenly spaced x values across `domain`
+pub fn get_bezier(points: &[AnimationKey], domain: RangeInclusive<f64>, curve_resolution: usize) -> Vec<[f64; 2]> {
+    let mut pts: Vec<[f64; 2]> = Vec::new();
 
-    // find the two pts we are looking at
-    let mut index_1 = 0;
-    for chunk in pts.windows(2) {
-        if chunk[0].pos.x <= time && chunk[1].pos.x >= time {
-            break;
-        }
-        index_1 += 1;
+    for i in 0..=curve_resolution {
+        let t = i as f64 / curve_resolution as f64;
+        let x = domain.start() + (domain.end() - domain.start()) * t;
+        pts.push([x, evaluate(points, x)]);
     }
 
-    // translate time to a new t value in a 0-1 range between these 2 points
-    let delta = pts[index_1 + 1].pos.x - pts[index_1].pos.x;
-    let t = (time - pts[index_1].pos.x) / delta;
-
-    evaluate_pair_bezier(&pts[index_1], &pts[index_1 + 1], t).y
+    pts
 }
 
 /// Calculate the point (x,y) at t based on the cubic hermite curve equation.
 /// t is in [0.0,1.0]
 #[inline(always)]
-fn evaluate_pair_hermite(pt1: &AnimationKey, pt2: &AnimationKey, time: f32) -> f32 {
+fn evaluate_pair_hermite(pt1: &AnimationKey, pt2: &AnimationKey, time: f64) -> f64 {
     let squared = time * time;
     let cubed = time * squared;
 
@@ -58,7 +80,7 @@ fn evaluate_pair_hermite(pt1: &AnimationKey, pt2: &AnimationKey, time: f32) -> f
 }
 
 #[inline(always)]
-fn evaluate_pair_hermite_first_derivative(pt1: &AnimationKey, pt2: &AnimationKey, time: f32) -> f32 {
+fn evaluate_pair_hermite_first_derivative(pt1: &AnimationKey, pt2: &AnimationKey, time: f64) -> f64 {
     let t2 = time * time;
 
     let tan1 = pt1.tangent_out.y / pt1.tangent_out.x;
@@ -69,30 +91,18 @@ fn evaluate_pair_hermite_first_derivative(pt1: &AnimationKey, pt2: &AnimationKey
         + (3. * t2 - 2. * time) * tan2
 }
 
-/// Calculate the point (x,y) at t based on the cubic Bézier curve equation.
-/// t is in [0.0,1.0]
-pub fn evaluate_pair_bezier(pt1: &AnimationKey, pt2: &AnimationKey, time: f32) -> Vec2 {
-    let h = 1.0 - time;
-    let a = time * time * time;
-    let b = 3.0 * time * time * h;
-    let c = 3.0 * time * h * h;
-    let d = h * h * h;
-
-    pt2.pos * a + pt2.tangent_in_world() * b + pt1.tangent_out_world() * c + pt1.pos * d
-}
-
 /// find a set of points that approximate the quadratic Bézier curve. the number of points is determined by the tolerance.
 /// the points may not be evenly distributed in the range [0.0,1.0] (t value)
-pub fn flatten(points: &Vec<AnimationKey>, tolerance: Option<f32>) -> Vec<[f64; 2]> {
-    let mut flat_pts = vec![[points[0].pos.x as f64, points[0].pos.y as f64]];
+pub fn flatten(points: &[AnimationKey], tolerance: Option<f32>) -> Vec<[f64; 2]> {
+    let mut flat_pts = vec![[points[0].pos.x, points[0].pos.y]];
 
     for chunk in points.windows(2) {
         let shape = CubicBezierShape {
             points: [
-                chunk[0].pos.to_pos2(),
-                chunk[0].tangent_out_world().to_pos2(),
-                chunk[1].tangent_in_world().to_pos2(),
-                chunk[1].pos.to_pos2(),
+                to_pos2(chunk[0].pos),
+                to_pos2(chunk[0].tangent_out_world()),
+                to_pos2(chunk[1].tangent_in_world()),
+                to_pos2(chunk[1].pos),
             ],
             closed: false,
             fill: Color32::RED,
@@ -112,3 +122,99 @@ pub fn flatten(points: &Vec<AnimationKey>, tolerance: Option<f32>) -> Vec<[f64;
 
     flat_pts
 }
+
+/// evenly spaced samples a curve with modifiers is drawn with, since modifiers like noise aren't Bézier segments
+const MODIFIED_CURVE_SAMPLES: usize = 2048;
+
+/// The curve with its `modifiers` as a polyline across `domain`, since cycles carry it past the keys. Without any
+/// enabled modifiers this is `flatten`, which only covers the keys.
+pub fn flatten_modified(
+    points: &[AnimationKey],
+    modifiers: &[Modifier],
+    domain: RangeInclusive<f64>,
+    tolerance: Option<f32>,
+) -> Vec<[f64; 2]> {
+    if points.is_empty() {
+        return vec![];
+    }
+    if !modifiers.iter().any(|modifier| modifier.enabled) {
+        return flatten(points, tolerance);
+    }
+    let (start, end) = (*domain.start(), *domain.end());
+    (0..=MODIFIED_CURVE_SAMPLES)
+        .map(|i| {
+            let x = start + (end - start) * i as f64 / MODIFIED_CURVE_SAMPLES as f64;
+            [x, evaluate_modified(points, modifiers, x)]
+        })
+        .collect()
+}
+
+/// flatten tolerance for arc length tables as a fraction of the size of the curve, which keeps lengths within about
+/// 0.01% of the true length
+pub const ARC_LENGTH_TOLERANCE: f64 = 1e-4;
+
+/// Arc length table over the polyline `flatten_modified` draws the curve with between its first and last key. The
+/// polyline is built in f32, so lengths of curves far from the origin lose some precision. With no tolerance
+/// `ARC_LENGTH_TOLERANCE` of the curve's size is used.
+pub fn arc_length_table(
+    points: &[AnimationKey],
+    modifiers: &[Modifier],
+    tolerance: Option<f32>,
+) -> ArcLengthTable<f64> {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return ArcLengthTable::default();
+    };
+    let tolerance = tolerance.unwrap_or_else(|| arc_length_tolerance(points));
+    ArcLengthTable::new(
+        flatten_modified(points, modifiers, first.pos.x..=last.pos.x, Some(tolerance))
+            .into_iter()
+            .map(|[x, y]| curve::vec2(x, y))
+            .collect(),
+    )
+}
+
+/// `arc_length_table` for a path, including the segment that closes it
+pub fn path_arc_length_table(path: &Path, tolerance: Option<f32>) -> ArcLengthTable<f64> {
+    let tolerance = tolerance.unwrap_or_else(|| arc_length_tolerance(&path.keys));
+    ArcLengthTable::new(
+        flatten_path(path, Some(tolerance))
+            .into_iter()
+            .map(|[x, y]| curve::vec2(x, y))
+            .collect(),
+    )
+}
+
+/// `ARC_LENGTH_TOLERANCE` of the largest extent of the keys and their handles
+fn arc_length_tolerance(points: &[AnimationKey]) -> f32 {
+    let (mut min, mut max) = (curve::vec2(f64::MAX, f64::MAX), curve::vec2(f64::MIN, f64::MIN));
+    for p in points
+        .iter()
+        .flat_map(|key| [key.pos, key.tangent_in_world(), key.tangent_out_world()])
+    {
+        min = curve::vec2(min.x.min(p.x), min.y.min(p.y));
+        max = curve::vec2(max.x.max(p.x), max.y.max(p.y));
+    }
+    let size = (max.x - min.x).max(max.y - min.y);
+    if size > 0.0 {
+        (size * ARC_LENGTH_TOLERANCE) as f32
+    } else {
+        ARC_LENGTH_TOLERANCE as f32
+    }
+}
+
+/// `flatten` for every segment of a path, including the one closing it
+pub fn flatten_path(path: &Path, tolerance: Option<f32>) -> Vec<[f64; 2]> {
+    if path.keys.is_empty() {
+        return vec![];
+    }
+    let mut flat_pts = flatten(&path.keys, tolerance);
+    if path.closed && path.keys.len() > 1 {
+        let closing = [path.keys[path.keys.len() - 1].clone(), path.keys[0].clone()];
+        flat_pts.extend(flatten(&closing, tolerance).into_iter().skip(1));
+    }
+    flat_pts
+}
+
+fn to_pos2(v: Vec2) -> Pos2 {
+    pos2(v.x as f32, v.y as f32)
+}
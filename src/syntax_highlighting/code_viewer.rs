@@ -1,15 +1,9 @@
 use egui::{vec2, Context, Response, Ui, Window};
 use egui_notify::Toasts;
 
-#[derive(PartialEq)]
+#[derive(Default, PartialEq)]
 pub struct CodeViewer {}
 
-impl Default for CodeViewer {
-    fn default() -> Self {
-        Self {}
-    }
-}
-
 impl super::super::Demo for CodeViewer {
     fn name(&self) -> &'static str {
         "🗠 Code Viewer"